@@ -0,0 +1,475 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Typed lookups that explain why they failed.
+//!
+//! [`Lookup`] reads a value at a [path](crate::path) and converts it to a
+//! Rust type in one step. Where [`Value::as_int`] and friends only return
+//! `None`, a [`LookupError`] names the full path, the type that was expected
+//! and the one that was found:
+//!
+//! ```
+//! use yini::{Lookup, Parser};
+//!
+//! let root = Parser::new("server {\n    port 70000\n}\n").parse();
+//! let threads = root.get_or("threads", 4).unwrap();
+//! assert_eq!(threads, 4);
+//! let error = root.req::<u16>("server.port").unwrap_err();
+//! assert_eq!(error.to_string(), "70000 does not fit in u16 at `server.port`");
+//! ```
+//!
+//! Integers are range checked when narrowed, and integers are accepted where
+//! a float is wanted as long as the float holds them exactly. A tree parsed
+//! with [`Parser::parse_spanned`](crate::Parser::parse_spanned) can be wrapped
+//! in [`Spanned`] so that errors also carry the source location.
+//!
+//! Other types take part by implementing [`FromValue`]. Errors from lookups
+//! inside [`FromValue::from_value`] are prefixed with the outer path.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::datetime::DateTime;
+use crate::path::{PathError, PathErrorKind, PathQuery, kind_name, locate};
+use crate::quantity::Quantity;
+use crate::span::{Position, StructSpans};
+use crate::{Struct, Value};
+
+/// Conversion from a [`Value`], used by [`Lookup`].
+pub trait FromValue<'a>: Sized {
+    /// Converts `value`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `value` is not of a kind that converts to `Self`. The error
+    /// path is relative to `value`, empty for `value` itself.
+    fn from_value(value: &'a Value) -> Result<Self, LookupError>;
+}
+
+/// Typed lookups by path, see the [module docs](self).
+pub trait Lookup {
+    /// The value at `path` converted to `T`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if nothing is at `path`, the path cannot be followed, or the
+    /// value does not convert to `T`.
+    fn req<'a, T: FromValue<'a>>(&'a self, path: &str) -> Result<T, LookupError>;
+
+    /// The value at `path` converted to `T`, `None` if it is missing or
+    /// `null`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the path cannot be followed or the value does not convert to
+    /// `T`.
+    fn opt<'a, T: FromValue<'a>>(&'a self, path: &str) -> Result<Option<T>, LookupError>;
+
+    /// The value at `path` converted to `T`, `default` if it is missing or
+    /// `null`.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`opt`](Self::opt).
+    fn get_or<'a, T: FromValue<'a>>(&'a self, path: &str, default: T) -> Result<T, LookupError> {
+        Ok(self.opt(path)?.unwrap_or(default))
+    }
+}
+
+/// A parsed tree together with its spans, so that lookup errors carry the
+/// source location.
+#[derive(Debug, Clone, Copy)]
+pub struct Spanned<'s> {
+    pub root: &'s Struct,
+    pub spans: &'s StructSpans,
+}
+
+impl<'s> Spanned<'s> {
+    #[must_use]
+    pub const fn new(root: &'s Struct, spans: &'s StructSpans) -> Self {
+        Self { root, spans }
+    }
+}
+
+/// Why a typed lookup failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupError {
+    /// The path of the value, such as `server.users[0].port`.
+    pub path: String,
+    /// Where the value starts, or for a missing value where the struct or
+    /// list it is missing from starts. Only known for lookups through
+    /// [`Spanned`].
+    pub position: Option<Position>,
+    pub kind: LookupErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupErrorKind {
+    /// Nothing at the path.
+    Missing,
+    /// The path could not be followed.
+    Path(PathErrorKind),
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A tuple with the wrong number of items.
+    Length { expected: usize, found: usize },
+    /// A number that does not fit the requested type. Holds the number and
+    /// the type.
+    OutOfRange { value: String, target: &'static str },
+}
+
+impl LookupError {
+    /// An error for `found`, which is not `expected`.
+    #[must_use]
+    pub const fn wrong_type(expected: &'static str, found: &Value) -> Self {
+        Self::new(LookupErrorKind::WrongType {
+            expected,
+            found: kind_name(found),
+        })
+    }
+
+    /// An error for `value`, which does not fit in `target`.
+    #[must_use]
+    pub fn out_of_range(value: impl fmt::Display, target: &'static str) -> Self {
+        Self::new(LookupErrorKind::OutOfRange {
+            value: value.to_string(),
+            target,
+        })
+    }
+
+    const fn new(kind: LookupErrorKind) -> Self {
+        Self {
+            path: String::new(),
+            position: None,
+            kind,
+        }
+    }
+
+    /// Puts `path` in front of the error path, for an error from inside the
+    /// value at `path`.
+    fn within(mut self, path: &str) -> Self {
+        self.path = if self.path.is_empty() {
+            path.to_owned()
+        } else if self.path.starts_with(['[', ':']) {
+            format!("{path}{}", self.path)
+        } else {
+            format!("{path}.{}", self.path)
+        };
+        self
+    }
+}
+
+/// What a path led to.
+enum Found<'a> {
+    Value(&'a Value),
+    /// Nothing, after following the first `depth` segments.
+    Missing {
+        depth: usize,
+    },
+}
+
+fn find<'a>(
+    found: Result<&'a Value, PathError>,
+    spans: Option<&StructSpans>,
+) -> Result<Found<'a>, LookupError> {
+    match found {
+        Ok(value) => Ok(Found::Value(value)),
+        Err(PathError {
+            segment,
+            kind: PathErrorKind::MissingKey(_) | PathErrorKind::IndexOutOfRange { .. },
+            ..
+        }) => Ok(Found::Missing { depth: segment }),
+        Err(error) => Err(LookupError {
+            position: position(spans, &error.at, error.segment),
+            path: error.at,
+            kind: LookupErrorKind::Path(error.kind),
+        }),
+    }
+}
+
+fn position(spans: Option<&StructSpans>, path: &str, depth: usize) -> Option<Position> {
+    locate(spans?, path, depth).map(|span| span.start)
+}
+
+fn convert<'a, T: FromValue<'a>>(
+    value: &'a Value,
+    path: &str,
+    spans: Option<&StructSpans>,
+) -> Result<T, LookupError> {
+    T::from_value(value).map_err(|error| {
+        let mut error = error.within(path);
+        error.position = position(spans, &error.path, usize::MAX);
+        error
+    })
+}
+
+fn req<'a, T: FromValue<'a>>(
+    found: Result<&'a Value, PathError>,
+    path: &str,
+    spans: Option<&StructSpans>,
+) -> Result<T, LookupError> {
+    match find(found, spans)? {
+        Found::Value(value) => convert(value, path, spans),
+        Found::Missing { depth } => Err(LookupError {
+            path: path.to_owned(),
+            position: position(spans, path, depth),
+            kind: LookupErrorKind::Missing,
+        }),
+    }
+}
+
+fn opt<'a, T: FromValue<'a>>(
+    found: Result<&'a Value, PathError>,
+    path: &str,
+    spans: Option<&StructSpans>,
+) -> Result<Option<T>, LookupError> {
+    match find(found, spans)? {
+        Found::Value(Value::Null) | Found::Missing { .. } => Ok(None),
+        Found::Value(value) => convert(value, path, spans).map(Some),
+    }
+}
+
+impl Lookup for Struct {
+    fn req<'a, T: FromValue<'a>>(&'a self, path: &str) -> Result<T, LookupError> {
+        req(self.get_path(path), path, None)
+    }
+
+    fn opt<'a, T: FromValue<'a>>(&'a self, path: &str) -> Result<Option<T>, LookupError> {
+        opt(self.get_path(path), path, None)
+    }
+}
+
+impl Lookup for Value {
+    fn req<'a, T: FromValue<'a>>(&'a self, path: &str) -> Result<T, LookupError> {
+        req(self.get_path(path), path, None)
+    }
+
+    fn opt<'a, T: FromValue<'a>>(&'a self, path: &str) -> Result<Option<T>, LookupError> {
+        opt(self.get_path(path), path, None)
+    }
+}
+
+impl Lookup for Spanned<'_> {
+    fn req<'a, T: FromValue<'a>>(&'a self, path: &str) -> Result<T, LookupError> {
+        req(self.root.get_path(path), path, Some(self.spans))
+    }
+
+    fn opt<'a, T: FromValue<'a>>(&'a self, path: &str) -> Result<Option<T>, LookupError> {
+        opt(self.root.get_path(path), path, Some(self.spans))
+    }
+}
+
+impl<'a> FromValue<'a> for &'a Value {
+    fn from_value(value: &'a Value) -> Result<Self, LookupError> {
+        Ok(value)
+    }
+}
+
+impl FromValue<'_> for Value {
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromValue<'_> for bool {
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        value
+            .as_bool()
+            .ok_or_else(|| LookupError::wrong_type("a boolean", value))
+    }
+}
+
+macro_rules! integer_from_value {
+    ($($int:ty)*) => {$(
+        impl FromValue<'_> for $int {
+            fn from_value(value: &Value) -> Result<Self, LookupError> {
+                let integer = value
+                    .as_i128()
+                    .ok_or_else(|| LookupError::wrong_type("an integer", value))?;
+                Self::try_from(integer)
+                    .map_err(|_| LookupError::out_of_range(integer, stringify!($int)))
+            }
+        }
+    )*};
+}
+
+integer_from_value!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+impl FromValue<'_> for f64 {
+    // Only integers that survive the round trip are promoted
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        match *value {
+            Value::Num(float) => Ok(float),
+            Value::Int(_) | Value::WideInt(_) => {
+                let integer = value.as_i128().unwrap_or_default();
+                let float = integer as Self;
+                if float as i128 == integer {
+                    Ok(float)
+                } else {
+                    Err(LookupError::out_of_range(integer, "f64"))
+                }
+            }
+            _ => Err(LookupError::wrong_type("a number", value)),
+        }
+    }
+}
+
+impl FromValue<'_> for f32 {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        let float = f64::from_value(value)?;
+        let narrowed = float as Self;
+        if narrowed.is_infinite() && float.is_finite() {
+            return Err(LookupError::out_of_range(float, "f32"));
+        }
+        Ok(narrowed)
+    }
+}
+
+impl<'a> FromValue<'a> for &'a str {
+    fn from_value(value: &'a Value) -> Result<Self, LookupError> {
+        value
+            .as_str()
+            .ok_or_else(|| LookupError::wrong_type("a string", value))
+    }
+}
+
+impl FromValue<'_> for String {
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        <&str>::from_value(value).map(str::to_owned)
+    }
+}
+
+impl<'a> FromValue<'a> for &'a [u8] {
+    fn from_value(value: &'a Value) -> Result<Self, LookupError> {
+        value
+            .as_bytes()
+            .ok_or_else(|| LookupError::wrong_type("bytes", value))
+    }
+}
+
+impl<'a> FromValue<'a> for &'a Struct {
+    fn from_value(value: &'a Value) -> Result<Self, LookupError> {
+        value
+            .as_struct()
+            .ok_or_else(|| LookupError::wrong_type("a struct", value))
+    }
+}
+
+impl FromValue<'_> for Struct {
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        <&Self>::from_value(value).cloned()
+    }
+}
+
+impl FromValue<'_> for Duration {
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        value
+            .as_duration()
+            .ok_or_else(|| LookupError::wrong_type("a duration", value))
+    }
+}
+
+impl FromValue<'_> for DateTime {
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        value
+            .as_datetime()
+            .copied()
+            .ok_or_else(|| LookupError::wrong_type("a date", value))
+    }
+}
+
+impl FromValue<'_> for Quantity {
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        value
+            .as_quantity()
+            .cloned()
+            .ok_or_else(|| LookupError::wrong_type("a quantity", value))
+    }
+}
+
+/// `null` is `None`.
+impl<'a, T: FromValue<'a>> FromValue<'a> for Option<T> {
+    fn from_value(value: &'a Value) -> Result<Self, LookupError> {
+        if value.is_null() {
+            Ok(None)
+        } else {
+            T::from_value(value).map(Some)
+        }
+    }
+}
+
+/// The items of an array or tuple.
+impl<'a, T: FromValue<'a>> FromValue<'a> for Vec<T> {
+    fn from_value(value: &'a Value) -> Result<Self, LookupError> {
+        let (Value::Array(items) | Value::Tuple(items)) = value else {
+            return Err(LookupError::wrong_type("an array or tuple", value));
+        };
+        items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                T::from_value(item).map_err(|error| error.within(&format!("[{index}]")))
+            })
+            .collect()
+    }
+}
+
+macro_rules! tuple_from_value {
+    ($($len:literal => ($($name:ident $index:tt),*))*) => {$(
+        impl<'a, $($name: FromValue<'a>),*> FromValue<'a> for ($($name,)*) {
+            fn from_value(value: &'a Value) -> Result<Self, LookupError> {
+                let (Value::Array(items) | Value::Tuple(items)) = value else {
+                    return Err(LookupError::wrong_type(concat!("a tuple of ", $len), value));
+                };
+                if items.len() != $len {
+                    return Err(LookupError::new(LookupErrorKind::Length {
+                        expected: $len,
+                        found: items.len(),
+                    }));
+                }
+                Ok(($(
+                    $name::from_value(&items[$index])
+                        .map_err(|error| error.within(concat!("[", $index, "]")))?,
+                )*))
+            }
+        }
+    )*};
+}
+
+tuple_from_value! {
+    2 => (A 0, B 1)
+    3 => (A 0, B 1, C 2)
+    4 => (A 0, B 1, C 2, D 3)
+}
+
+impl fmt::Display for LookupErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => f.write_str("missing value"),
+            Self::Path(kind) => write!(f, "{kind}"),
+            Self::WrongType { expected, found } => write!(f, "expected {expected}, found {found}"),
+            Self::Length { expected, found } => {
+                write!(f, "expected {expected} items, found {found}")
+            }
+            Self::OutOfRange { value, target } => write!(f, "{value} does not fit in {target}"),
+        }
+    }
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at `{}`", self.kind, self.path)?;
+        if let Some(position) = self.position {
+            write!(f, ", line {}, column {}", position.line, position.column)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LookupError {}
@@ -0,0 +1,518 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Lossless concrete syntax tree.
+//!
+//! [`Parser::parse_cst`](crate::Parser::parse_cst) keeps everything the value
+//! tree throws away: comments, blank lines, indentation, optional `key:`
+//! colons, commas and the exact spelling of every scalar. Printing a
+//! [`SyntaxTree`] with `to_string()` gives back the input byte for byte, even
+//! when it had errors; text the parser skipped is kept as
+//! [`TriviaKind::Skipped`].
+//!
+//! Trivia after a field or item up to and including the end of its line is
+//! stored as that node's `trailing` trivia, so a comment at the end of a line
+//! stays with the value on that line.
+
+use std::fmt;
+
+use seq_map::SeqMap;
+
+use crate::span::{FieldSpan, SpanChildren, StructSpans, ValueSpan};
+use crate::{Struct, Value};
+
+/// A lossless parse of a whole document.
+#[derive(Debug, Clone)]
+pub struct SyntaxTree {
+    pub root: CstStruct,
+}
+
+/// What a piece of trivia is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces and tabs, and a leading byte order mark.
+    Whitespace,
+    /// A single line break: `\n`, `\r\n` or `\r`.
+    Newline,
+    /// A `#` comment, without the line break that ends it.
+    Comment,
+    /// A `,` between values.
+    Comma,
+    /// Text the parser reported an error for and skipped.
+    Skipped,
+}
+
+/// Source text that carries no data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+/// The fields of the document root or of a `{ ... }` block.
+#[derive(Debug, Clone, Default)]
+pub struct CstStruct {
+    pub fields: Vec<CstField>,
+    /// Trivia after the last field, before the closing brace.
+    pub trailing: Vec<Trivia>,
+    /// Whether the closing brace is present. Always `false` for the root.
+    pub closed: bool,
+}
+
+/// A `key value` line.
+#[derive(Debug, Clone)]
+pub struct CstField {
+    pub leading: Vec<Trivia>,
+    pub key: CstKey,
+    /// Whether the key is followed by the optional `:`.
+    pub colon: bool,
+    /// Trivia between the key (and colon) and the value.
+    pub before_value: Vec<Trivia>,
+    pub value: CstValue,
+    pub trailing: Vec<Trivia>,
+}
+
+/// A field key as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstKey {
+    /// The key the value tree uses.
+    pub name: String,
+    /// The source text, including quotes if the key was quoted.
+    pub raw: String,
+}
+
+/// A value and its syntax.
+#[derive(Debug, Clone)]
+pub enum CstValue {
+    Scalar(CstScalar),
+    Struct(CstStruct),
+    Array(CstList),
+    Tuple(CstList),
+    Variant(CstVariant),
+}
+
+/// A string, number or boolean as written.
+#[derive(Debug, Clone)]
+pub struct CstScalar {
+    /// The source text, including quotes and escapes.
+    pub raw: String,
+    pub value: Value,
+}
+
+/// The items of a `[ ... ]` array or `( ... )` tuple.
+#[derive(Debug, Clone, Default)]
+pub struct CstList {
+    pub items: Vec<CstItem>,
+    /// Trivia after the last item, before the closing bracket.
+    pub trailing: Vec<Trivia>,
+    /// Whether the closing bracket is present.
+    pub closed: bool,
+}
+
+/// An array or tuple item.
+#[derive(Debug, Clone)]
+pub struct CstItem {
+    pub leading: Vec<Trivia>,
+    pub value: CstValue,
+    pub trailing: Vec<Trivia>,
+}
+
+/// A `:name` variant with its optional payload.
+#[derive(Debug, Clone)]
+pub struct CstVariant {
+    pub name: String,
+    pub payload: Option<Box<CstValue>>,
+}
+
+/// The base an integer is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Decimal,
+    Hex,
+}
+
+impl Radix {
+    /// `value` written in this base, with its `0b`, `0o` or `0x` prefix.
+    #[must_use]
+    pub fn format(self, value: i128) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        let magnitude = value.unsigned_abs();
+        match self {
+            Self::Binary => format!("{sign}0b{magnitude:b}"),
+            Self::Octal => format!("{sign}0o{magnitude:o}"),
+            Self::Decimal => value.to_string(),
+            Self::Hex => format!("{sign}0x{magnitude:x}"),
+        }
+    }
+}
+
+impl CstScalar {
+    /// The base of an integer scalar, `None` for other values.
+    #[must_use]
+    pub fn radix(&self) -> Option<Radix> {
+        if !matches!(self.value, Value::Int(_) | Value::WideInt(_)) {
+            return None;
+        }
+        let unsigned = self.raw.trim_start_matches(['+', '-']);
+        Some(match unsigned.get(..2) {
+            Some("0b" | "0B") => Radix::Binary,
+            Some("0o" | "0O") => Radix::Octal,
+            Some("0x" | "0X") => Radix::Hex,
+            _ => Radix::Decimal,
+        })
+    }
+
+    /// Whether the scalar was written as a quoted string, including
+    /// `"""` and raw strings.
+    #[must_use]
+    pub fn is_quoted(&self) -> bool {
+        self.raw.starts_with('"')
+            || self
+                .raw
+                .strip_prefix('r')
+                .is_some_and(|rest| rest.trim_start_matches('#').starts_with('"'))
+    }
+}
+
+impl CstStruct {
+    /// The field with key `name`. Like the parser, the first of several
+    /// fields with the same key wins.
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&CstField> {
+        self.fields.iter().find(|field| field.key.name == name)
+    }
+
+    /// The value tree this syntax describes.
+    #[must_use]
+    pub fn to_struct(&self) -> Struct {
+        let mut map = SeqMap::new();
+        for field in &self.fields {
+            let _ = map.insert(field.key.name.clone(), field.value.to_value());
+        }
+        map
+    }
+}
+
+impl CstValue {
+    /// The value this syntax describes.
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Scalar(scalar) => scalar.value.clone(),
+            Self::Struct(fields) => Value::Struct(fields.to_struct()),
+            Self::Array(list) => Value::Array(list.to_values()),
+            Self::Tuple(list) => Value::Tuple(list.to_values()),
+            Self::Variant(variant) => Value::Variant(
+                variant.name.clone(),
+                variant
+                    .payload
+                    .as_ref()
+                    .map(|payload| Box::new(payload.to_value())),
+            ),
+        }
+    }
+}
+
+impl CstList {
+    pub(crate) fn to_values(&self) -> Vec<Value> {
+        self.items
+            .iter()
+            .map(|item| item.value.to_value())
+            .collect()
+    }
+}
+
+impl SyntaxTree {
+    /// The value tree this syntax describes.
+    #[must_use]
+    pub fn to_struct(&self) -> Struct {
+        self.root.to_struct()
+    }
+}
+
+impl fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_fields(f, &self.root)
+    }
+}
+
+impl fmt::Display for CstValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scalar(scalar) => f.write_str(&scalar.raw),
+            Self::Struct(fields) => {
+                f.write_str("{")?;
+                write_fields(f, fields)?;
+                if fields.closed {
+                    f.write_str("}")?;
+                }
+                Ok(())
+            }
+            Self::Array(list) => write_list(f, list, "[", "]"),
+            Self::Tuple(list) => write_list(f, list, "(", ")"),
+            Self::Variant(variant) => {
+                write!(f, ":{}", variant.name)?;
+                if let Some(payload) = &variant.payload {
+                    write!(f, "{payload}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_trivia(f: &mut fmt::Formatter<'_>, trivia: &[Trivia]) -> fmt::Result {
+    for piece in trivia {
+        f.write_str(&piece.text)?;
+    }
+    Ok(())
+}
+
+fn write_fields(f: &mut fmt::Formatter<'_>, fields: &CstStruct) -> fmt::Result {
+    for field in &fields.fields {
+        write_trivia(f, &field.leading)?;
+        f.write_str(&field.key.raw)?;
+        if field.colon {
+            f.write_str(":")?;
+        }
+        write_trivia(f, &field.before_value)?;
+        write!(f, "{}", field.value)?;
+        write_trivia(f, &field.trailing)?;
+    }
+    write_trivia(f, &fields.trailing)
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, list: &CstList, open: &str, close: &str) -> fmt::Result {
+    f.write_str(open)?;
+    for item in &list.items {
+        write_trivia(f, &item.leading)?;
+        write!(f, "{}", item.value)?;
+        write_trivia(f, &item.trailing)?;
+    }
+    write_trivia(f, &list.trailing)?;
+    if list.closed {
+        f.write_str(close)?;
+    }
+    Ok(())
+}
+
+/// Builds the syntax tree for `source` from its value and span trees.
+pub(crate) fn build(source: &str, root: &Struct, spans: &StructSpans) -> SyntaxTree {
+    let builder = Builder { source };
+    SyntaxTree {
+        root: builder.fields(root, spans, 0, source.len(), false),
+    }
+}
+
+struct Builder<'a> {
+    source: &'a str,
+}
+
+impl Builder<'_> {
+    /// The fields between `start` and `end`, where `end` is just before the
+    /// closing brace if there is one.
+    fn fields(
+        &self,
+        values: &Struct,
+        spans: &StructSpans,
+        start: usize,
+        end: usize,
+        closed: bool,
+    ) -> CstStruct {
+        let mut fields: Vec<CstField> = Vec::with_capacity(spans.len());
+        let mut cursor = start;
+        for ((name, value), field_span) in values.iter().zip(spans.values()) {
+            let FieldSpan { key, value: span } = field_span;
+            let leading = self.gap(
+                cursor,
+                key.start.offset,
+                fields.last_mut().map(|f| &mut f.trailing),
+            );
+            let mut after_key = key.end.offset;
+            let colon = self.source.as_bytes().get(after_key) == Some(&b':');
+            if colon {
+                after_key += 1;
+            }
+            fields.push(CstField {
+                leading,
+                key: CstKey {
+                    name: name.clone(),
+                    raw: self.source[key.range()].to_owned(),
+                },
+                colon,
+                before_value: trivia(&self.source[after_key..span.span.start.offset]),
+                value: self.value(value, span),
+                trailing: Vec::new(),
+            });
+            cursor = span.span.end.offset;
+        }
+        let trailing = self.gap(cursor, end, fields.last_mut().map(|f| &mut f.trailing));
+        CstStruct {
+            fields,
+            trailing,
+            closed,
+        }
+    }
+
+    fn items(
+        &self,
+        values: &[Value],
+        spans: &[ValueSpan],
+        start: usize,
+        end: usize,
+        bracket: u8,
+    ) -> CstList {
+        let last = spans.last().map(|s| s.span.end.offset);
+        let (end, closed) = self.close(start, last, end, bracket);
+        let mut items: Vec<CstItem> = Vec::with_capacity(spans.len());
+        let mut cursor = start;
+        for (value, span) in values.iter().zip(spans) {
+            let leading = self.gap(
+                cursor,
+                span.span.start.offset,
+                items.last_mut().map(|i| &mut i.trailing),
+            );
+            items.push(CstItem {
+                leading,
+                value: self.value(value, span),
+                trailing: Vec::new(),
+            });
+            cursor = span.span.end.offset;
+        }
+        let trailing = self.gap(cursor, end, items.last_mut().map(|i| &mut i.trailing));
+        CstList {
+            items,
+            trailing,
+            closed,
+        }
+    }
+
+    fn value(&self, value: &Value, span: &ValueSpan) -> CstValue {
+        let range = span.span.range();
+        match (value, &span.children) {
+            (Value::Struct(fields), SpanChildren::Fields(spans)) => {
+                let last = spans.values().last().map(|f| f.value.span.end.offset);
+                let (end, closed) = self.close(range.start + 1, last, range.end, b'}');
+                CstValue::Struct(self.fields(fields, spans, range.start + 1, end, closed))
+            }
+            (Value::Array(items), SpanChildren::Items(spans)) => {
+                CstValue::Array(self.items(items, spans, range.start + 1, range.end, b']'))
+            }
+            (Value::Tuple(items), SpanChildren::Items(spans)) => {
+                CstValue::Tuple(self.items(items, spans, range.start + 1, range.end, b')'))
+            }
+            (Value::Variant(name, payload), SpanChildren::Variant { payload: span, .. }) => {
+                CstValue::Variant(CstVariant {
+                    name: name.clone(),
+                    payload: payload
+                        .as_deref()
+                        .zip(span.as_deref())
+                        .map(|(payload, span)| Box::new(self.value(payload, span))),
+                })
+            }
+            _ => CstValue::Scalar(CstScalar {
+                raw: self.source[range].to_owned(),
+                value: value.clone(),
+            }),
+        }
+    }
+
+    /// Where the contents of a container running to `end` stop, and whether
+    /// it ends with its own closing `bracket` rather than at the end of the
+    /// input. A bracket of another kind stays in the contents as skipped text.
+    fn close(
+        &self,
+        start: usize,
+        last_child_end: Option<usize>,
+        end: usize,
+        bracket: u8,
+    ) -> (usize, bool) {
+        let after_children = last_child_end.unwrap_or(start).min(end);
+        let closed = trivia(&self.source[after_children..end])
+            .last()
+            .is_some_and(|last| {
+                last.kind == TriviaKind::Skipped && last.text.as_bytes().last() == Some(&bracket)
+            });
+        if closed {
+            (end - 1, true)
+        } else {
+            (end, false)
+        }
+    }
+
+    /// The trivia between `start` and `end`. The part up to and including the
+    /// first line break belongs to the previous node, if there is one.
+    fn gap(&self, start: usize, end: usize, previous: Option<&mut Vec<Trivia>>) -> Vec<Trivia> {
+        let mut pieces = trivia(&self.source[start..end]);
+        if let Some(previous) = previous {
+            let line_end = pieces
+                .iter()
+                .position(|piece| piece.kind == TriviaKind::Newline)
+                .map_or(pieces.len(), |index| index + 1);
+            previous.extend(pieces.drain(..line_end));
+        }
+        pieces
+    }
+}
+
+/// Splits text between values into trivia.
+fn trivia(text: &str) -> Vec<Trivia> {
+    let bytes = text.as_bytes();
+    let mut pieces = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        let kind = match bytes[pos] {
+            b'\n' => {
+                pos += 1;
+                TriviaKind::Newline
+            }
+            b'\r' => {
+                pos += if bytes.get(pos + 1) == Some(&b'\n') {
+                    2
+                } else {
+                    1
+                };
+                TriviaKind::Newline
+            }
+            b',' => {
+                pos += 1;
+                TriviaKind::Comma
+            }
+            b' ' | b'\t' => {
+                while pos < bytes.len() && matches!(bytes[pos], b' ' | b'\t') {
+                    pos += 1;
+                }
+                TriviaKind::Whitespace
+            }
+            // A byte order mark starting the document
+            0xEF if text[pos..].starts_with('\u{feff}') => {
+                pos += '\u{feff}'.len_utf8();
+                TriviaKind::Whitespace
+            }
+            b'#' => {
+                while pos < bytes.len() && !matches!(bytes[pos], b'\n' | b'\r') {
+                    pos += 1;
+                }
+                TriviaKind::Comment
+            }
+            _ => {
+                while pos < bytes.len()
+                    && !matches!(bytes[pos], b' ' | b'\t' | b'\r' | b'\n' | b'#')
+                {
+                    pos += 1;
+                }
+                TriviaKind::Skipped
+            }
+        };
+        pieces.push(Trivia {
+            kind,
+            text: text[start..pos].to_owned(),
+        });
+    }
+    pieces
+}
@@ -0,0 +1,461 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Dates, times and durations.
+//!
+//! Dates and date-times are written in RFC 3339 form and read as
+//! [`Value::DateTime`](crate::Value::DateTime):
+//!
+//! ```text
+//! released 2024-05-01
+//! backup   2024-05-01T03:00:00
+//! deadline 2024-05-01T17:30:00.250+02:00
+//! ```
+//!
+//! A number followed by a unit is a [`Value::Duration`](crate::Value::Duration).
+//! The units are `ns`, `us`, `ms`, `s`, `m`, `h` and `d`, and several can be
+//! combined, as in `250ms`, `1.5s` or `1h30m`.
+//!
+//! With the `chrono` feature, [`DateTime`] converts to and from the matching
+//! `chrono` types.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A calendar date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// 0 to 9999.
+    pub year: u16,
+    /// 1 to 12.
+    pub month: u8,
+    /// 1 to the length of the month.
+    pub day: u8,
+}
+
+/// A time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    pub hour: u8,
+    pub minute: u8,
+    /// 0 to 60, where 60 is a leap second.
+    pub second: u8,
+    pub nanosecond: u32,
+}
+
+/// How far a date-time is from UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Offset {
+    /// `Z`
+    Utc,
+    /// `+02:00`, in minutes east of UTC.
+    Minutes(i16),
+}
+
+/// A date, optionally with a time of day and an offset from UTC.
+///
+/// An offset is only allowed together with a time. Without one the time is
+/// local to wherever the file is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DateTime {
+    pub date: Date,
+    pub time: Option<Time>,
+    pub offset: Option<Offset>,
+}
+
+/// Why text is not a valid date-time, or a [`DateTime`] does not fit a
+/// `chrono` type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateTimeError(&'static str);
+
+impl fmt::Display for DateTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for DateTimeError {}
+
+/// Converts a number read or taken from elsewhere to a field's type, failing
+/// with `message` if it does not fit.
+fn narrow<T: TryFrom<U>, U>(value: U, message: &'static str) -> Result<T, DateTimeError> {
+    T::try_from(value).map_err(|_| DateTimeError(message))
+}
+
+impl Date {
+    /// The largest year that is written with the four digits that are read.
+    const MAX_YEAR: u16 = 9999;
+
+    const fn check(self) -> Result<(), DateTimeError> {
+        if self.year > Self::MAX_YEAR {
+            return Err(DateTimeError("year must be 0 to 9999"));
+        }
+        if self.month < 1 || self.month > 12 {
+            return Err(DateTimeError("month must be 1 to 12"));
+        }
+        if self.day == 0 || self.day > self.days_in_month() {
+            return Err(DateTimeError("day is not in the month"));
+        }
+        Ok(())
+    }
+
+    const fn days_in_month(self) -> u8 {
+        match self.month {
+            2 if self.year.is_multiple_of(4)
+                && (!self.year.is_multiple_of(100) || self.year.is_multiple_of(400)) =>
+            {
+                29
+            }
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+}
+
+impl Time {
+    const fn check(self) -> Result<(), DateTimeError> {
+        if self.hour > 23 || self.minute > 59 || self.second > 60 {
+            return Err(DateTimeError("time of day is out of range"));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = DateTimeError;
+
+    fn from_str(text: &str) -> Result<Self, DateTimeError> {
+        let mut reader = Reader(text.as_bytes());
+        let year = reader.number(4)?;
+        reader.expect(b'-')?;
+        let month = reader.number(2)?;
+        reader.expect(b'-')?;
+        let day = reader.number(2)?;
+        let date = Date {
+            year: narrow(year, "year must be 0 to 9999")?,
+            month: narrow(month, "month must be 1 to 12")?,
+            day: narrow(day, "day is not in the month")?,
+        };
+        date.check()?;
+
+        let mut date_time = Self {
+            date,
+            time: None,
+            offset: None,
+        };
+        if reader.0.is_empty() {
+            return Ok(date_time);
+        }
+        if !matches!(reader.0.first(), Some(b'T' | b't')) {
+            return Err(DateTimeError("expected `T` between the date and the time"));
+        }
+        reader.0 = &reader.0[1..];
+        let hour = reader.number(2)?;
+        reader.expect(b':')?;
+        let minute = reader.number(2)?;
+        reader.expect(b':')?;
+        let second = reader.number(2)?;
+        let nanosecond = if reader.0.first() == Some(&b'.') {
+            reader.0 = &reader.0[1..];
+            let digits = reader.0.iter().take_while(|b| b.is_ascii_digit()).count();
+            if digits == 0 || digits > 9 {
+                return Err(DateTimeError("fractional seconds must have 1 to 9 digits"));
+            }
+            let fraction = reader.number(digits)?;
+            let missing: u32 = narrow(9 - digits, "fractional seconds must have 1 to 9 digits")?;
+            fraction * 10_u32.pow(missing)
+        } else {
+            0
+        };
+        let time = Time {
+            hour: narrow(hour, "time of day is out of range")?,
+            minute: narrow(minute, "time of day is out of range")?,
+            second: narrow(second, "time of day is out of range")?,
+            nanosecond,
+        };
+        time.check()?;
+        date_time.time = Some(time);
+
+        date_time.offset = match reader.0.first() {
+            None => None,
+            Some(b'Z' | b'z') => {
+                reader.0 = &reader.0[1..];
+                Some(Offset::Utc)
+            }
+            Some(&sign @ (b'+' | b'-')) => {
+                reader.0 = &reader.0[1..];
+                let hours = reader.number(2)?;
+                reader.expect(b':')?;
+                let minutes = reader.number(2)?;
+                if hours > 23 || minutes > 59 {
+                    return Err(DateTimeError("offset is out of range"));
+                }
+                let total: i16 = narrow(hours * 60 + minutes, "offset is out of range")?;
+                Some(Offset::Minutes(if sign == b'-' { -total } else { total }))
+            }
+            Some(_) => return Err(DateTimeError("expected `Z` or an offset after the time")),
+        };
+        if reader.0.is_empty() {
+            Ok(date_time)
+        } else {
+            Err(DateTimeError("unexpected text after the date-time"))
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    /// Reads exactly `digits` decimal digits.
+    fn number(&mut self, digits: usize) -> Result<u32, DateTimeError> {
+        let Some(text) = self
+            .0
+            .get(..digits)
+            .filter(|text| text.iter().all(u8::is_ascii_digit))
+        else {
+            return Err(DateTimeError("expected a number"));
+        };
+        self.0 = &self.0[digits..];
+        Ok(text
+            .iter()
+            .fold(0, |value, b| value * 10 + u32::from(b - b'0')))
+    }
+
+    const fn expect(&mut self, byte: u8) -> Result<(), DateTimeError> {
+        match self.0.split_first() {
+            Some((b, rest)) if *b == byte => {
+                self.0 = rest;
+                Ok(())
+            }
+            _ => Err(DateTimeError(match byte {
+                b'-' => "expected `-` in the date",
+                _ => "expected `:` in the time",
+            })),
+        }
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Date { year, month, day } = self.date;
+        write!(f, "{year:04}-{month:02}-{day:02}")?;
+        let Some(time) = self.time else {
+            return Ok(());
+        };
+        write!(f, "T{:02}:{:02}:{:02}", time.hour, time.minute, time.second)?;
+        if time.nanosecond != 0 {
+            let fraction = format!("{:09}", time.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        match self.offset {
+            None => Ok(()),
+            Some(Offset::Utc) => f.write_str("Z"),
+            Some(Offset::Minutes(minutes)) => {
+                let sign = if minutes < 0 { '-' } else { '+' };
+                let minutes = minutes.unsigned_abs();
+                write!(f, "{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+            }
+        }
+    }
+}
+
+/// Nanoseconds per duration unit.
+const UNITS: [(&str, u128); 7] = [
+    ("d", 86_400_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// Whether `text` ends in a duration unit, so that it is meant as a duration
+/// rather than a number.
+pub(crate) fn has_duration_unit(text: &str) -> bool {
+    let unit_start = text
+        .rfind(|c: char| !c.is_ascii_alphabetic())
+        .map_or(0, |index| index + 1);
+    UNITS.iter().any(|(name, _)| *name == &text[unit_start..])
+}
+
+/// Reads a duration such as `250ms`, `1.5s` or `1h30m`.
+#[must_use]
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let mut rest = text;
+    let mut nanos: u128 = 0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '_' || c == '.'))
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_len);
+        let unit_len = after
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_len);
+        let scale = UNITS.iter().find(|(name, _)| *name == unit)?.1;
+
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if !crate::is_digit_group(whole, 10) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let whole: u128 = whole.replace('_', "").parse().ok()?;
+        nanos = nanos.checked_add(whole.checked_mul(scale)?)?;
+        if !fraction.is_empty() {
+            let denominator = 10_u128.checked_pow(u32::try_from(fraction.len()).ok()?)?;
+            let part = fraction.parse::<u128>().ok()?.checked_mul(scale)?;
+            // Anything finer than a nanosecond can not be stored
+            if part % denominator != 0 {
+                return None;
+            }
+            nanos = nanos.checked_add(part / denominator)?;
+        }
+        rest = after;
+    }
+    let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+    let subsec = u32::try_from(nanos % 1_000_000_000).ok()?;
+    (!text.is_empty()).then(|| Duration::new(secs, subsec))
+}
+
+/// Writes `duration` in the largest unit that holds it exactly, such as
+/// `90m` or `1500ms`.
+#[must_use]
+pub fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_string();
+    }
+    let (name, scale) = UNITS
+        .iter()
+        .find(|(_, scale)| nanos.is_multiple_of(*scale))
+        .unwrap_or(&UNITS[UNITS.len() - 1]);
+    format!("{}{name}", nanos / scale)
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_conversions {
+    use chrono::{Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+    use super::{Date, DateTime, DateTimeError, Offset, Time, narrow};
+
+    impl TryFrom<NaiveDate> for DateTime {
+        type Error = DateTimeError;
+
+        /// Fails for a year before 0 or after 9999, which yini can not write.
+        fn try_from(date: NaiveDate) -> Result<Self, DateTimeError> {
+            let date = Date {
+                year: narrow(date.year(), "year must be 0 to 9999")?,
+                month: narrow(date.month(), "month must be 1 to 12")?,
+                day: narrow(date.day(), "day is not in the month")?,
+            };
+            date.check()?;
+            Ok(Self {
+                date,
+                time: None,
+                offset: None,
+            })
+        }
+    }
+
+    impl TryFrom<NaiveDateTime> for DateTime {
+        type Error = DateTimeError;
+
+        /// Fails for a year before 0 or after 9999, which yini can not write.
+        fn try_from(date_time: NaiveDateTime) -> Result<Self, DateTimeError> {
+            let time = date_time.time();
+            // chrono keeps a leap second as an extra second of nanoseconds
+            let (second, nanosecond) = if time.nanosecond() >= 1_000_000_000 {
+                (60, time.nanosecond() - 1_000_000_000)
+            } else {
+                (time.second(), time.nanosecond())
+            };
+            let time = Time {
+                hour: narrow(time.hour(), "time of day is out of range")?,
+                minute: narrow(time.minute(), "time of day is out of range")?,
+                second: narrow(second, "time of day is out of range")?,
+                nanosecond,
+            };
+            time.check()?;
+            Ok(Self {
+                time: Some(time),
+                ..date_time.date().try_into()?
+            })
+        }
+    }
+
+    impl TryFrom<chrono::DateTime<FixedOffset>> for DateTime {
+        type Error = DateTimeError;
+
+        /// Fails for a year before 0 or after 9999, or an offset that is not
+        /// a whole number of minutes.
+        fn try_from(date_time: chrono::DateTime<FixedOffset>) -> Result<Self, DateTimeError> {
+            let seconds = date_time.offset().local_minus_utc();
+            if seconds % 60 != 0 {
+                return Err(DateTimeError("offset must be whole minutes"));
+            }
+            Ok(Self {
+                offset: Some(Offset::Minutes(narrow(
+                    seconds / 60,
+                    "offset is out of range",
+                )?)),
+                ..date_time.naive_local().try_into()?
+            })
+        }
+    }
+
+    impl TryFrom<DateTime> for NaiveDate {
+        type Error = DateTimeError;
+
+        fn try_from(date_time: DateTime) -> Result<Self, DateTimeError> {
+            let Date { year, month, day } = date_time.date;
+            Self::from_ymd_opt(i32::from(year), u32::from(month), u32::from(day))
+                .ok_or(DateTimeError("date is out of range"))
+        }
+    }
+
+    impl TryFrom<DateTime> for NaiveDateTime {
+        type Error = DateTimeError;
+
+        /// Fails for a date without a time. Any offset is dropped.
+        fn try_from(date_time: DateTime) -> Result<Self, DateTimeError> {
+            let date = NaiveDate::try_from(date_time)?;
+            let time = date_time.time.ok_or(DateTimeError("date has no time"))?;
+            let (second, nanosecond) = if time.second == 60 {
+                (59, time.nanosecond + 1_000_000_000)
+            } else {
+                (time.second, time.nanosecond)
+            };
+            let time = NaiveTime::from_hms_nano_opt(
+                u32::from(time.hour),
+                u32::from(time.minute),
+                u32::from(second),
+                nanosecond,
+            )
+            .ok_or(DateTimeError("time of day is out of range"))?;
+            Ok(date.and_time(time))
+        }
+    }
+
+    impl TryFrom<DateTime> for chrono::DateTime<FixedOffset> {
+        type Error = DateTimeError;
+
+        /// Fails unless the date-time has both a time and an offset.
+        fn try_from(date_time: DateTime) -> Result<Self, DateTimeError> {
+            let minutes = match date_time.offset {
+                Some(Offset::Utc) => 0,
+                Some(Offset::Minutes(minutes)) => i32::from(minutes),
+                None => return Err(DateTimeError("date-time has no offset")),
+            };
+            let offset = FixedOffset::east_opt(minutes * 60)
+                .ok_or(DateTimeError("offset is out of range"))?;
+            NaiveDateTime::try_from(date_time)?
+                .and_local_timezone(offset)
+                .single()
+                .ok_or(DateTimeError("date-time is out of range"))
+        }
+    }
+}
@@ -0,0 +1,880 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Serde deserialization, either from a parsed [`Struct`]/[`Value`] or
+//! directly from yini text.
+//!
+//! Variants map to Rust enums: `:name` is a unit variant, `:name(a b)` a tuple
+//! variant, `:name{...}` a struct variant and `:name(x)` a newtype variant.
+//! Tuples map to tuples and tuple structs, arrays to sequences and structs to
+//! maps and structs.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use serde::de::value::{BorrowedStrDeserializer, SeqDeserializer, StringDeserializer};
+use serde::de::{
+    self, DeserializeSeed, Deserializer as _, EnumAccess, Error as _, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use crate::{ErrorKind, ParseError, Parser, Struct, Value};
+
+/// A deserialization error, with the location of the offending value when known.
+#[derive(Debug, Clone)]
+pub struct Error {
+    message: String,
+    location: Option<(usize, usize)>,
+}
+
+impl Error {
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// One-based line of the offending value, if the error came from text.
+    #[must_use]
+    pub fn line(&self) -> Option<usize> {
+        self.location.map(|(line, _)| line)
+    }
+
+    /// One-based column of the offending value, if the error came from text.
+    #[must_use]
+    pub fn column(&self) -> Option<usize> {
+        self.location.map(|(_, column)| column)
+    }
+
+    /// Attaches a location unless a more precise one is already set.
+    const fn or_at(mut self, line: usize, column: usize) -> Self {
+        if self.location.is_none() {
+            self.location = Some((line, column));
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "{} at line {line}, column {column}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            location: None,
+        }
+    }
+}
+
+impl From<&ParseError> for Error {
+    fn from(error: &ParseError) -> Self {
+        Self {
+            message: error.kind.to_string(),
+            location: Some((error.line, error.column)),
+        }
+    }
+}
+
+/// Deserializes `T` from yini text without building an intermediate tree.
+///
+/// # Errors
+///
+/// Returns the first parse error, or a type mismatch located at the offending value.
+pub fn from_str<'de, T: de::Deserialize<'de>>(input: &'de str) -> Result<T, Error> {
+    let mut deserializer = Deserializer::from_str(input);
+    T::deserialize(&mut deserializer)
+}
+
+/// Deserializes `T` from an already parsed document.
+///
+/// # Errors
+///
+/// Returns an error if the tree does not match the shape of `T`.
+pub fn from_struct<'de, T: de::Deserialize<'de>>(root: &'de Struct) -> Result<T, Error> {
+    T::deserialize(StructDeserializer(root))
+}
+
+/// Deserializes `T` from a single value.
+///
+/// # Errors
+///
+/// Returns an error if the value does not match the shape of `T`.
+pub fn from_value<'de, T: de::Deserialize<'de>>(value: &'de Value) -> Result<T, Error> {
+    T::deserialize(value)
+}
+
+/// Where the next value is read from. Bare words end at different places
+/// depending on the surrounding syntax.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Root,
+    Field,
+    Array,
+    Tuple,
+}
+
+/// Deserializes directly from yini text.
+pub struct Deserializer<'de> {
+    parser: Parser<'de>,
+    context: Context,
+}
+
+impl<'de> Deserializer<'de> {
+    #[must_use]
+    #[allow(clippy::should_implement_trait)]
+    pub const fn from_str(input: &'de str) -> Self {
+        Self {
+            parser: Parser::new(input),
+            context: Context::Root,
+        }
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        self.parser
+            .errors
+            .first()
+            .map_or(Ok(()), |error| Err(error.into()))
+    }
+
+    fn error_here(&self, kind: ErrorKind) -> Error {
+        (&ParseError {
+            line: self.parser.line,
+            column: self.parser.column,
+            kind,
+        })
+            .into()
+    }
+
+    /// Parses a scalar (or anything else) the same way [`Parser`] does in the current context.
+    fn parse_scalar(&mut self) -> Result<Value, Error> {
+        let value = match self.context {
+            Context::Root | Context::Field => self.parser.parse_field_value(),
+            Context::Array => self.parser.parse_value(),
+            Context::Tuple => self.parser.parse_tuple_item(),
+        };
+        self.check()?;
+        Ok(value)
+    }
+
+    fn expect_byte(&mut self, expected: u8) -> Result<(), Error> {
+        match self.parser.peek_byte() {
+            Some(b) if b == expected => {
+                self.parser.next_byte();
+                Ok(())
+            }
+            Some(_) => {
+                let found = self.parser.peek_char().unwrap_or(' ');
+                let message = format!("expected `{}`, found {found:?}", char::from(expected));
+                Err(Error::custom(message).or_at(self.parser.line, self.parser.column))
+            }
+            None => Err(self.error_here(ErrorKind::UnexpectedEndOfInput)),
+        }
+    }
+
+    /// Visits the items up to `close`, which fixed-size visitors stop short of.
+    fn visit_items<V: Visitor<'de>>(&mut self, close: u8, visitor: V) -> Result<V::Value, Error> {
+        let mut access = ItemAccess {
+            de: self,
+            close,
+            done: false,
+        };
+        let value = visitor.visit_seq(&mut access)?;
+        if !access.done {
+            self.parser.skip_ws_and_comments();
+            if self.parser.peek_byte() != Some(close) {
+                return Err(Error::custom("too many items"));
+            }
+            self.parser.next_byte();
+        }
+        Ok(value)
+    }
+
+    fn deserialize_at<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, Error> {
+        if self.context == Context::Root {
+            return visitor.visit_map(FieldAccess {
+                de: self,
+                closed: false,
+            });
+        }
+        match self.parser.peek_byte() {
+            Some(b'{') => {
+                self.parser.next_byte();
+                visitor.visit_map(FieldAccess {
+                    de: self,
+                    closed: true,
+                })
+            }
+            Some(b'[') => {
+                self.parser.next_byte();
+                self.visit_items(b']', visitor)
+            }
+            Some(b'(') => {
+                self.parser.next_byte();
+                self.visit_items(b')', visitor)
+            }
+            Some(b':') => visitor.visit_enum(self),
+            _ => {
+                let value = self.parse_scalar()?;
+                visit_scalar(value, visitor)
+            }
+        }
+    }
+}
+
+fn visit_scalar<'de, V: Visitor<'de>>(value: Value, visitor: V) -> Result<V::Value, Error> {
+    match value {
+        Value::Null => visitor.visit_unit(),
+        Value::Str(s) => visitor.visit_string(s),
+        Value::Bytes(b) => visitor.visit_byte_buf(b),
+        Value::Int(i) => visitor.visit_i64(i),
+        Value::WideInt(i) => visit_wide_int(i, visitor),
+        Value::Num(n) => visitor.visit_f64(n),
+        Value::Bool(b) => visitor.visit_bool(b),
+        Value::DateTime(d) => visitor.visit_string(d.to_string()),
+        Value::Duration(d) => visit_duration(d, visitor),
+        Value::Quantity(q) => visitor.visit_string(q.to_string()),
+        other => Err(Error::invalid_type(unexpected(&other), &visitor)),
+    }
+}
+
+/// Visits an integer outside the `i64` range as a `u64` where it fits, so
+/// that `u64` fields accept it, and as an `i128` otherwise.
+fn visit_wide_int<'de, V: Visitor<'de>>(value: i128, visitor: V) -> Result<V::Value, Error> {
+    match u64::try_from(value) {
+        Ok(u) => visitor.visit_u64(u),
+        Err(_) => visitor.visit_i128(value),
+    }
+}
+
+/// Visits a duration the way `std::time::Duration` deserializes, as
+/// `(secs, nanos)`.
+fn visit_duration<'de, V: Visitor<'de>>(
+    value: std::time::Duration,
+    visitor: V,
+) -> Result<V::Value, Error> {
+    let parts = [value.as_secs(), u64::from(value.subsec_nanos())];
+    visitor.visit_seq(SeqDeserializer::new(parts.into_iter()))
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let (line, column) = (self.parser.line, self.parser.column);
+        self.deserialize_at(visitor)
+            .map_err(|error| error.or_at(line, column))
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // Strings keep the parser's rest-of-line reading, even when they start like a container
+        if self.context == Context::Root {
+            return self.deserialize_any(visitor);
+        }
+        let (line, column) = (self.parser.line, self.parser.column);
+        self.parse_scalar()
+            .and_then(|value| visit_scalar(value, visitor))
+            .map_err(|error| error.or_at(line, column))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // `null` is `None`, anything else is read again as the value
+        if self.parser.peek_byte() == Some(b'n') {
+            let (pos, line, column) = (self.parser.pos, self.parser.line, self.parser.column);
+            let errors = self.parser.errors.len();
+            if matches!(self.parse_scalar(), Ok(Value::Null)) {
+                return visitor.visit_none();
+            }
+            self.parser.errors.truncate(errors);
+            (self.parser.pos, self.parser.line, self.parser.column) = (pos, line, column);
+        }
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let (line, column) = (self.parser.line, self.parser.column);
+        self.expect_byte(b'(')
+            .and_then(|()| {
+                self.parser.skip_ws_and_comments();
+                self.expect_byte(b')')
+            })
+            .and_then(|()| visitor.visit_unit())
+            .map_err(|error| error.or_at(line, column))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let (line, column) = (self.parser.line, self.parser.column);
+        let result = if self.parser.peek_byte() == Some(b':') {
+            visitor.visit_enum(&mut *self)
+        } else {
+            // A plain word names a unit variant
+            match self.parse_scalar() {
+                Ok(Value::Str(name)) => visitor.visit_enum(StringDeserializer::<Error>::new(name)),
+                Ok(other) => visit_scalar(other, visitor),
+                Err(error) => Err(error),
+            }
+        };
+        result.map_err(|error| error.or_at(line, column))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let (line, column) = (self.parser.line, self.parser.column);
+        self.parse_scalar()
+            .map_err(|error| error.or_at(line, column))?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // Byte literals fill sequences such as `Vec<u8>` too
+        if !self.parser.at_bytes() {
+            return self.deserialize_any(visitor);
+        }
+        let (line, column) = (self.parser.line, self.parser.column);
+        self.parse_scalar()
+            .and_then(|value| match value {
+                Value::Bytes(b) => visitor.visit_seq(SeqDeserializer::new(b.into_iter())),
+                other => visit_scalar(other, visitor),
+            })
+            .map_err(|error| error.or_at(line, column))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+        bytes byte_buf tuple tuple_struct map struct identifier
+    }
+}
+
+/// Reads `key value` lines, either at the top level or inside `{ }`.
+struct FieldAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    closed: bool,
+}
+
+impl<'de> MapAccess<'de> for FieldAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let parser = &mut self.de.parser;
+        parser.skip_ws_and_comments();
+        match parser.peek_byte() {
+            None if self.closed => return Err(self.de.error_here(ErrorKind::UnterminatedBlock)),
+            None => return Ok(None),
+            Some(b'}') if self.closed => {
+                parser.next_byte();
+                return Ok(None);
+            }
+            Some(_) => {}
+        }
+
+        let (line, column) = (parser.line, parser.column);
+        let Some(key) = parser.parse_key() else {
+            let ch = parser.peek_byte().map_or(' ', char::from);
+            return Err(self.de.error_here(ErrorKind::UnexpectedCharacter(ch)));
+        };
+        self.de.check()?;
+
+        // Colon is optional - but must be *immediately* after key (no whitespace)
+        let parser = &mut self.de.parser;
+        if parser.peek_byte() == Some(b':') {
+            parser.next_byte();
+        }
+        parser.skip_horizontal_ws();
+        if parser.at_line_end() || parser.is_eof() {
+            return Err(self.de.error_here(ErrorKind::ExpectedValueOnSameLine));
+        }
+
+        seed.deserialize(KeyDeserializer(Cow::Owned(key)))
+            .map(Some)
+            .map_err(|error: Error| error.or_at(line, column))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        self.de.context = Context::Field;
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.parser.require_newline_or_eof();
+        self.de.check()?;
+        Ok(value)
+    }
+}
+
+/// Reads the items of an array `[ ]` or a tuple `( )`.
+struct ItemAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    close: u8,
+    done: bool,
+}
+
+impl<'de> SeqAccess<'de> for ItemAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.de.parser.skip_ws_and_comments();
+        match self.de.parser.peek_byte() {
+            None => Err(self.de.error_here(ErrorKind::UnexpectedEndOfInput)),
+            Some(b',') => Err(self.de.error_here(ErrorKind::UnexpectedComma)),
+            Some(b) if b == self.close => {
+                self.de.parser.next_byte();
+                self.done = true;
+                Ok(None)
+            }
+            Some(_) => {
+                self.de.context = if self.close == b']' {
+                    Context::Array
+                } else {
+                    Context::Tuple
+                };
+                let value = seed.deserialize(&mut *self.de)?;
+                self.de.parser.skip_ws_and_comments();
+                self.de.parser.skip_comma();
+                Ok(Some(value))
+            }
+        }
+    }
+}
+
+impl<'de> EnumAccess<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        self.expect_byte(b':')?;
+        let name = self.parser.parse_variant_name();
+        let value = seed.deserialize(StringDeserializer::<Error>::new(name))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.parser.peek_byte() {
+            Some(b'(') => de::Deserializer::deserialize_unit(self, de::IgnoredAny).map(|_| ()),
+            Some(b'{' | b'[') => Err(Error::invalid_type(
+                de::Unexpected::NewtypeVariant,
+                &"unit variant",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.parser.peek_byte() {
+            Some(b'(') => {
+                self.parser.next_byte();
+                self.parser.skip_ws_and_comments();
+                self.context = Context::Tuple;
+                let value = seed.deserialize(&mut *self)?;
+                self.parser.skip_ws_and_comments();
+                self.parser.skip_comma();
+                self.expect_byte(b')')?;
+                Ok(value)
+            }
+            Some(b'{' | b'[') => {
+                self.context = Context::Array;
+                seed.deserialize(self)
+            }
+            _ => Err(Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.expect_byte(b'(')?;
+        self.visit_items(b')', visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.expect_byte(b'{')?;
+        visitor.visit_map(FieldAccess {
+            de: self,
+            closed: true,
+        })
+    }
+}
+
+/// Deserializes from the fields of a parsed document.
+pub struct StructDeserializer<'de>(pub &'de Struct);
+
+impl<'de> de::Deserializer<'de> for StructDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(StructAccess {
+            iter: self.0.iter(),
+            value: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Str(s) => visitor.visit_borrowed_str(s),
+            Value::Bytes(b) => visitor.visit_borrowed_bytes(b),
+            Value::Int(i) => visitor.visit_i64(*i),
+            Value::WideInt(i) => visit_wide_int(*i, visitor),
+            Value::Num(n) => visitor.visit_f64(*n),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::DateTime(d) => visitor.visit_string(d.to_string()),
+            Value::Duration(d) => visit_duration(*d, visitor),
+            Value::Quantity(q) => visitor.visit_string(q.to_string()),
+            Value::Variant(..) => visitor.visit_enum(self),
+            Value::Struct(fields) => StructDeserializer(fields).deserialize_any(visitor),
+            Value::Array(items) | Value::Tuple(items) => {
+                visitor.visit_seq(ValueSeqAccess { iter: items.iter() })
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Tuple(items) if items.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            // A plain word names a unit variant
+            Value::Str(name) => visitor.visit_enum(BorrowedStrDeserializer::<Error>::new(name)),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            // Byte literals fill sequences such as `Vec<u8>` too
+            Value::Bytes(b) => visitor.visit_seq(SeqDeserializer::new(b.iter().copied())),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf tuple tuple_struct map struct identifier
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+struct StructAccess<'de, I> {
+    iter: I,
+    value: Option<&'de Value>,
+}
+
+impl<'de, I> MapAccess<'de> for StructAccess<'de, I>
+where
+    I: Iterator<Item = (&'de String, &'de Value)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(KeyDeserializer(Cow::Borrowed(key)))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::custom("value requested before key"))?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.iter.size_hint().1
+    }
+}
+
+/// Deserializes a map key. Keys are always text, so integer, boolean and
+/// character keys, as written by the serializer, are parsed back from it.
+struct KeyDeserializer<'de>(Cow<'de, str>);
+
+macro_rules! parse_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.parse() {
+                    Ok(key) => visitor.$visit(key),
+                    Err(_) => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Cow::Borrowed(key) => visitor.visit_borrowed_str(key),
+            Cow::Owned(key) => visitor.visit_string(key),
+        }
+    }
+
+    parse_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.0 {
+            Cow::Borrowed(key) => {
+                BorrowedStrDeserializer::new(key).deserialize_enum(name, variants, visitor)
+            }
+            Cow::Owned(key) => {
+                StringDeserializer::new(key).deserialize_enum(name, variants, visitor)
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        f32 f64 str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct ValueSeqAccess<'de> {
+    iter: std::slice::Iter<'de, Value>,
+}
+
+impl<'de> SeqAccess<'de> for ValueSeqAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.iter
+            .next()
+            .map_or(Ok(None), |value| seed.deserialize(value).map(Some))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+impl<'de> EnumAccess<'de> for &'de Value {
+    type Error = Error;
+    type Variant = PayloadAccess<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let Value::Variant(name, payload) = self else {
+            return Err(Error::invalid_type(unexpected(self), &"variant"));
+        };
+        let value = seed.deserialize(BorrowedStrDeserializer::<Error>::new(name))?;
+        Ok((value, PayloadAccess(payload.as_deref())))
+    }
+}
+
+/// The optional payload of a [`Value::Variant`].
+pub struct PayloadAccess<'de>(Option<&'de Value>);
+
+impl<'de> VariantAccess<'de> for PayloadAccess<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.0 {
+            None => Ok(()),
+            Some(Value::Tuple(items)) if items.is_empty() => Ok(()),
+            Some(other) => Err(Error::invalid_type(unexpected(other), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.0 {
+            Some(Value::Tuple(items)) if items.len() == 1 => seed.deserialize(&items[0]),
+            Some(payload) => seed.deserialize(payload),
+            None => Err(Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Some(Value::Tuple(items) | Value::Array(items)) => {
+                visitor.visit_seq(ValueSeqAccess { iter: items.iter() })
+            }
+            Some(other) => Err(Error::invalid_type(unexpected(other), &"tuple variant")),
+            None => Err(Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.0 {
+            Some(Value::Struct(fields)) => StructDeserializer(fields).deserialize_any(visitor),
+            Some(other) => Err(Error::invalid_type(unexpected(other), &"struct variant")),
+            None => Err(Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+fn unexpected(value: &Value) -> de::Unexpected<'_> {
+    match value {
+        Value::Null => de::Unexpected::Unit,
+        Value::Str(s) => de::Unexpected::Str(s),
+        Value::Bytes(b) => de::Unexpected::Bytes(b),
+        Value::Int(i) => de::Unexpected::Signed(*i),
+        Value::WideInt(i) => u64::try_from(*i).map_or(
+            de::Unexpected::Other("128-bit integer"),
+            de::Unexpected::Unsigned,
+        ),
+        Value::Num(n) => de::Unexpected::Float(*n),
+        Value::Bool(b) => de::Unexpected::Bool(*b),
+        Value::DateTime(_) => de::Unexpected::Other("date"),
+        Value::Duration(_) => de::Unexpected::Other("duration"),
+        Value::Quantity(_) => de::Unexpected::Other("quantity"),
+        Value::Variant(..) => de::Unexpected::Enum,
+        Value::Struct(_) => de::Unexpected::Map,
+        Value::Array(_) | Value::Tuple(_) => de::Unexpected::Seq,
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Renders parse errors as annotated source snippets.
+//!
+//! ```text
+//! error: expected a value on the same line as the key
+//!  --> settings.yini:2:5
+//!   |
+//! 2 | host
+//!   |     ^
+//!   = help: values must start on the same line as the key
+//! ```
+
+use std::fmt::Write as _;
+
+use crate::{ErrorKind, ParseError};
+
+/// How diagnostics are rendered.
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticOptions {
+    /// Color the output with ANSI escape codes.
+    pub color: bool,
+    /// Shown in front of the line and column, usually the file name.
+    pub origin: Option<String>,
+}
+
+/// Renders `errors` against the `source` they were reported for.
+#[must_use]
+pub fn render_diagnostics(source: &str, errors: &[ParseError]) -> String {
+    render_diagnostics_with(source, errors, &DiagnosticOptions::default())
+}
+
+/// Renders `errors` against the `source` they were reported for using `options`.
+#[must_use]
+pub fn render_diagnostics_with(
+    source: &str,
+    errors: &[ParseError],
+    options: &DiagnosticOptions,
+) -> String {
+    let gutter = errors
+        .iter()
+        .map(|error| error.line.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let style = Style::new(options.color);
+    let mut out = String::new();
+    for (index, error) in errors.iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        render_one(&mut out, source, error, options, gutter, &style);
+    }
+    out
+}
+
+impl ErrorKind {
+    /// A suggestion for fixing the error, if there is a common fix.
+    #[must_use]
+    pub const fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::ExpectedValueOnSameLine => Some("values must start on the same line as the key"),
+            Self::ExpectedNewlineAfterKeyValue(_) => {
+                Some("put each field on its own line, or start a comment with `#`")
+            }
+            Self::UnterminatedBlock => Some("every `{` needs a matching `}`"),
+            Self::UnterminatedString => Some("quotes inside a string are written `\\\"`"),
+            Self::InvalidIntegerFormat(_) => Some(
+                "integers use the digits of their base, with `_` only between digits, and fit in 128 bits",
+            ),
+            Self::UnexpectedCharacter(_) => Some("keys are plain words or quoted strings"),
+            Self::UnexpectedEndOfInput => Some("an array or tuple is missing its closing bracket"),
+            Self::DuplicateKey { .. } => Some("each key can only appear once in a struct"),
+            Self::UnquotedString(_) => {
+                Some("quote text with spaces, or check for a missing line break")
+            }
+            Self::InvalidBareWord(_) => Some(
+                "bare words are letters, digits, `_`, `-`, `.` and `/`, starting with a letter",
+            ),
+            Self::UnexpectedComma => Some("items are separated by a single comma or whitespace"),
+            Self::InvalidEscape(_) => Some(
+                "escapes are `\\n`, `\\r`, `\\t`, `\\0`, `\\\"`, `\\\\`, `\\x00` to `\\x7F` and `\\u{...}`",
+            ),
+            Self::InvalidDateTime(_) => {
+                Some("dates are written `2024-05-01`, optionally followed by a time `T12:30:00Z`")
+            }
+            Self::InvalidDuration(_) => Some(
+                "durations are a positive number followed by `ns`, `us`, `ms`, `s`, `m`, `h` or `d`",
+            ),
+            Self::InvalidByteDigit { .. } => {
+                Some("hex uses `0-9` and `a-f`, base64 uses `A-Z`, `a-z`, `0-9`, `+` and `/`")
+            }
+            Self::IncompleteBytes(_) => Some(
+                "hex needs two digits per byte, base64 groups of four characters padded with `=`",
+            ),
+            Self::InvalidUtf8InNumber | Self::InvalidFloatFormat(_) => None,
+        }
+    }
+
+    /// How many characters the error points at, ending at its column when
+    /// `ends_at_column`, else starting there.
+    fn width(&self) -> (usize, bool) {
+        match self {
+            Self::InvalidIntegerFormat(text)
+            | Self::InvalidFloatFormat(text)
+            | Self::InvalidDateTime(text)
+            | Self::InvalidDuration(text) => (text.chars().count(), true),
+            Self::DuplicateKey { key, .. }
+            | Self::UnquotedString(key)
+            | Self::InvalidBareWord(key)
+            | Self::InvalidEscape(key) => (key.chars().count(), false),
+            _ => (1, false),
+        }
+    }
+}
+
+struct Style {
+    error: &'static str,
+    accent: &'static str,
+    bold: &'static str,
+    reset: &'static str,
+}
+
+impl Style {
+    const fn new(color: bool) -> Self {
+        if color {
+            Self {
+                error: "\x1b[1;31m",
+                accent: "\x1b[1;34m",
+                bold: "\x1b[1m",
+                reset: "\x1b[0m",
+            }
+        } else {
+            Self {
+                error: "",
+                accent: "",
+                bold: "",
+                reset: "",
+            }
+        }
+    }
+}
+
+fn render_one(
+    out: &mut String,
+    source: &str,
+    error: &ParseError,
+    options: &DiagnosticOptions,
+    gutter: usize,
+    style: &Style,
+) {
+    let Style {
+        error: red,
+        accent: blue,
+        bold,
+        reset,
+    } = style;
+    let pad = " ".repeat(gutter);
+    let text = source_line(source, error.line);
+
+    let _ = writeln!(out, "{red}error{reset}{bold}: {}{reset}", error.kind);
+    let origin = options
+        .origin
+        .as_deref()
+        .map_or_else(String::new, |origin| format!("{origin}:"));
+    let _ = writeln!(
+        out,
+        "{pad}{blue}-->{reset} {origin}{}:{}",
+        error.line, error.column
+    );
+    let _ = writeln!(out, "{pad} {blue}|{reset}");
+    let _ = writeln!(out, "{blue}{:>gutter$} |{reset} {text}", error.line);
+
+    // Columns count bytes, so find the character the error starts at
+    let (width, ends_at_column) = error.kind.width();
+    let mut start = error.column.saturating_sub(1).min(text.len());
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    if ends_at_column {
+        let before: Vec<(usize, char)> = text[..start].char_indices().collect();
+        start = before
+            .len()
+            .checked_sub(width)
+            .map_or(0, |index| before[index].0);
+    }
+    let indent: String = text[..start]
+        .chars()
+        .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+        .collect();
+    let _ = writeln!(
+        out,
+        "{pad} {blue}|{reset} {indent}{red}{}{reset}",
+        "^".repeat(width.max(1))
+    );
+    if let Some(hint) = error.kind.hint() {
+        let _ = writeln!(out, "{pad} {blue}={reset} {bold}help{reset}: {hint}");
+    }
+}
+
+/// Line `line` of `source`, counting lines the way the parser does.
+fn source_line(source: &str, line: usize) -> &str {
+    let mut rest = source.strip_prefix('\u{feff}').unwrap_or(source);
+    for _ in 1..line {
+        let Some(end) = rest.find(['\n', '\r']) else {
+            return "";
+        };
+        let break_len = if rest[end..].starts_with("\r\n") {
+            2
+        } else {
+            1
+        };
+        rest = &rest[end + break_len..];
+    }
+    rest.find(['\n', '\r']).map_or(rest, |end| &rest[..end])
+}
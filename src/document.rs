@@ -0,0 +1,745 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Format-preserving editing.
+//!
+//! A [`Document`] wraps a lossless [`SyntaxTree`]. Edits only touch the
+//! values they name: comments, blank lines, indentation and the spelling of
+//! everything else are written back exactly as they were read. New values are
+//! laid out by the [writer](crate::writer), indented to match their
+//! surroundings.
+//!
+//! Paths are keys separated by dots. A number selects an item of an array or
+//! tuple, and a variant is stepped through to reach its payload:
+//!
+//! ```
+//! use yini::Value;
+//! use yini::document::Document;
+//!
+//! let mut doc = Document::parse("server {\n    port 8080 # public\n}\n").unwrap();
+//! doc.set("server.port", Value::Int(9090)).unwrap();
+//! assert_eq!(doc.to_string(), "server {\n    port 9090 # public\n}\n");
+//! ```
+
+use std::fmt;
+
+use seq_map::SeqMap;
+
+use crate::cst::{
+    CstField, CstItem, CstList, CstScalar, CstStruct, CstValue, Radix, SyntaxTree, Trivia,
+    TriviaKind,
+};
+use crate::writer::{self, Indent, LineEnding, WriteOptions};
+use crate::{Errors, Parser, Value};
+
+/// A parsed document that can be edited and written back with its formatting intact.
+#[derive(Debug, Clone)]
+pub struct Document {
+    tree: SyntaxTree,
+    layout: Layout,
+}
+
+/// How added lines are laid out, following the parsed text.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    indent: Indent,
+    line_ending: LineEnding,
+}
+
+/// Why an edit could not be made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// Nothing exists at this path.
+    NotFound(String),
+    /// The value at this path is not the kind the edit needs.
+    WrongKind {
+        path: String,
+        expected: &'static str,
+    },
+    /// The path is empty or has an empty key, as in `server.`.
+    EmptyKey(String),
+    /// The value could not be written as yini text that reads back as one
+    /// field.
+    Unwritable(String),
+    /// The name would not read back as a variant name.
+    InvalidVariantName(String),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(path) => write!(f, "nothing at `{path}`"),
+            Self::WrongKind { path, expected } => write!(f, "`{path}` is not {expected}"),
+            Self::EmptyKey(path) => write!(f, "`{path}` has an empty key"),
+            Self::Unwritable(path) => write!(f, "the value for `{path}` cannot be written"),
+            Self::InvalidVariantName(name) => write!(f, "`{name}` is not a valid variant name"),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+impl Document {
+    /// Parses `text`, failing if the parser reported any errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns the parse errors if `text` is not a valid document.
+    pub fn parse(text: &str) -> Result<Self, Errors> {
+        let mut parser = Parser::new(text);
+        let tree = parser.parse_cst();
+        if !parser.errors().is_empty() {
+            return Err(parser.errors.into());
+        }
+        Ok(Self {
+            tree,
+            layout: Layout {
+                indent: detect_indent(text),
+                line_ending: LineEnding::detect(text),
+            },
+        })
+    }
+
+    /// The syntax tree with all edits applied.
+    #[must_use]
+    pub const fn syntax(&self) -> &SyntaxTree {
+        &self.tree
+    }
+
+    /// The value at `path`.
+    #[must_use]
+    pub fn get(&self, path: &str) -> Option<Value> {
+        let mut segments = path.split('.');
+        let first = segments.next()?;
+        let mut value = &self.tree.root.field(first)?.value;
+        for segment in segments {
+            value = child(value, segment)?;
+        }
+        Some(value.to_value())
+    }
+
+    /// Replaces the value at `path`, or adds it as a new field. Missing
+    /// structs along the way are created.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `path` has an empty key, or if a parent of `path` is neither
+    /// a struct nor, for a number, an array or tuple with that many items.
+    pub fn set(&mut self, path: &str, value: impl Into<Value>) -> Result<(), EditError> {
+        let segments: Vec<&str> = path.split('.').collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(EditError::EmptyKey(path.to_owned()));
+        }
+        let unwritable = || EditError::Unwritable(path.to_owned());
+        let (parent, name) = segments.split_at(segments.len() - 1);
+        let layout = self.layout;
+        let mut value = value.into();
+
+        let mut target = Target::Struct(&mut self.tree.root);
+        let mut outer = String::new();
+        for (index, segment) in parent.iter().enumerate() {
+            if let Target::Struct(fields) = &mut target
+                && fields.field(segment).is_none()
+            {
+                // Wrap the value in structs for the rest of the path
+                for key in segments[index + 1..].iter().rev() {
+                    let mut wrapper = SeqMap::new();
+                    let _ = wrapper.insert((*key).to_owned(), value);
+                    value = Value::Struct(wrapper);
+                }
+                return fields
+                    .append(segment, &value, &outer, layout)
+                    .ok_or_else(unwritable);
+            }
+            (target, outer) = target.enter(segment, &outer, layout).ok_or_else(|| {
+                wrong_kind(
+                    &segments[..=index],
+                    "a struct, array or tuple with that item",
+                )
+            })?;
+        }
+
+        match target {
+            Target::Struct(fields) => {
+                if let Some(index) = fields.position(name[0]) {
+                    let line_indent = field_indent(fields, index)
+                        .unwrap_or_else(|| struct_indent(fields, &outer, layout));
+                    let field = &mut fields.fields[index];
+                    field.value = render_replacement(&field.value, &value, &line_indent, layout)
+                        .ok_or_else(unwritable)?;
+                    Ok(())
+                } else {
+                    fields
+                        .append(name[0], &value, &outer, layout)
+                        .ok_or_else(unwritable)
+                }
+            }
+            Target::List(list, tuple) => {
+                let item_indent = list_indent(list, &outer, layout);
+                let item = name[0]
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| list.items.get_mut(index))
+                    .ok_or_else(|| EditError::NotFound(path.to_owned()))?;
+                item.value = match keep_radix(&item.value, &value) {
+                    Some(value) => value,
+                    None => render_item(&value, &item_indent, layout).ok_or_else(unwritable)?,
+                };
+                if tuple {
+                    break_run_on_words(list, &outer, layout).ok_or_else(unwritable)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Appends `value` to the array or tuple at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no array or tuple at `path`.
+    pub fn push(&mut self, path: &str, value: impl Into<Value>) -> Result<(), EditError> {
+        let layout = self.layout;
+        let (list, tuple, outer) = match self.target(path)? {
+            (Target::List(list, tuple), outer) => (list, tuple, outer),
+            (Target::Struct(_), _) => {
+                return Err(EditError::WrongKind {
+                    path: path.to_owned(),
+                    expected: "an array or tuple",
+                });
+            }
+        };
+        let item_indent = list_indent(list, &outer, layout);
+        let unwritable = || EditError::Unwritable(path.to_owned());
+        let rendered = render_item(&value.into(), &item_indent, layout).ok_or_else(unwritable)?;
+        list.push(rendered, layout.line_ending);
+        if tuple {
+            break_run_on_words(list, &outer, layout).ok_or_else(unwritable)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the field or item at `path` together with the comments directly
+    /// above it and on its line, returning its value.
+    pub fn remove(&mut self, path: &str) -> Option<Value> {
+        let (target, name) = self.parent(path).ok()?;
+        match target {
+            Target::Struct(fields) => {
+                let index = fields.fields.iter().position(|f| f.key.name == name)?;
+                let removed = fields.fields.remove(index);
+                let next = fields
+                    .fields
+                    .get_mut(index)
+                    .map_or(&mut fields.trailing, |next| &mut next.leading);
+                keep_leading(next, &removed.leading, index == 0);
+                Some(removed.value.to_value())
+            }
+            Target::List(list, _) => {
+                let index = name.parse::<usize>().ok()?;
+                if index >= list.items.len() {
+                    return None;
+                }
+                let removed = list.items.remove(index);
+                if index == list.items.len() && index > 0 {
+                    // The new last item takes over the separator before the closing bracket
+                    list.items[index - 1].trailing = removed.trailing;
+                }
+                let next = list
+                    .items
+                    .get_mut(index)
+                    .map_or(&mut list.trailing, |next| &mut next.leading);
+                keep_leading(next, &removed.leading, index == 0);
+                Some(removed.value.to_value())
+            }
+        }
+    }
+
+    /// Renames the variant at `path`, keeping its payload.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no variant at `path`, or if `name` is empty or holds
+    /// whitespace or punctuation that would end it early.
+    pub fn rename_variant(&mut self, path: &str, name: &str) -> Result<(), EditError> {
+        if !crate::is_variant_name(name) {
+            return Err(EditError::InvalidVariantName(name.to_owned()));
+        }
+        let (target, key) = self.parent(path)?;
+        let value = match target {
+            Target::Struct(fields) => fields.field_mut(key).map(|field| &mut field.value),
+            Target::List(list, _) => key
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| list.items.get_mut(index))
+                .map(|item| &mut item.value),
+        };
+        match value {
+            Some(CstValue::Variant(variant)) => {
+                name.clone_into(&mut variant.name);
+                Ok(())
+            }
+            Some(_) => Err(EditError::WrongKind {
+                path: path.to_owned(),
+                expected: "a variant",
+            }),
+            None => Err(EditError::NotFound(path.to_owned())),
+        }
+    }
+
+    /// The container holding the last segment of `path`, and that segment.
+    fn parent<'p>(&mut self, path: &'p str) -> Result<(Target<'_>, &'p str), EditError> {
+        match path.rsplit_once('.') {
+            Some((parent, last)) => Ok((self.target(parent)?.0, last)),
+            None => Ok((Target::Struct(&mut self.tree.root), path)),
+        }
+    }
+
+    /// The container at `path`, with the indentation of the line it starts on.
+    fn target(&mut self, path: &str) -> Result<(Target<'_>, String), EditError> {
+        let segments: Vec<&str> = path.split('.').collect();
+        let mut target = Target::Struct(&mut self.tree.root);
+        let mut outer = String::new();
+        for (index, segment) in segments.iter().enumerate() {
+            if let Target::Struct(fields) = &target
+                && fields.field(segment).is_none()
+            {
+                return Err(EditError::NotFound(segments[..=index].join(".")));
+            }
+            (target, outer) = target
+                .enter(segment, &outer, self.layout)
+                .ok_or_else(|| wrong_kind(&segments[..=index], "a struct, array or tuple"))?;
+        }
+        Ok((target, outer))
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tree)
+    }
+}
+
+/// A container that an edit walks into.
+enum Target<'a> {
+    Struct(&'a mut CstStruct),
+    /// An array, or a tuple when the flag is set.
+    List(&'a mut CstList, bool),
+}
+
+impl Target<'_> {
+    /// Steps into the container named by `segment`. `outer` is the indentation
+    /// of the line the current container starts on.
+    fn enter(self, segment: &str, outer: &str, layout: Layout) -> Option<(Self, String)> {
+        let (value, line_indent) = match self {
+            Self::Struct(fields) => {
+                let index = fields.position(segment)?;
+                let line_indent = field_indent(fields, index)
+                    .unwrap_or_else(|| struct_indent(fields, outer, layout));
+                (&mut fields.fields[index].value, line_indent)
+            }
+            Self::List(list, _) => {
+                let index = segment.parse::<usize>().ok()?;
+                list.items.get(index)?;
+                let line_indent =
+                    item_indent(list, index).unwrap_or_else(|| list_indent(list, outer, layout));
+                (&mut list.items[index].value, line_indent)
+            }
+        };
+        let mut value = value;
+        while let CstValue::Variant(variant) = value {
+            value = variant.payload.as_deref_mut()?;
+        }
+        match value {
+            CstValue::Struct(fields) => Some((Self::Struct(fields), line_indent)),
+            CstValue::Array(list) => Some((Self::List(list, false), line_indent)),
+            CstValue::Tuple(list) => Some((Self::List(list, true), line_indent)),
+            CstValue::Scalar(_) | CstValue::Variant(_) => None,
+        }
+    }
+}
+
+impl CstStruct {
+    fn position(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.key.name == name)
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut CstField> {
+        self.fields.iter_mut().find(|field| field.key.name == name)
+    }
+
+    /// Adds a new last field. `outer` is the indentation of the line the
+    /// struct starts on. Returns `None`, leaving the struct unchanged, if the
+    /// field could not be written.
+    fn append(&mut self, key: &str, value: &Value, outer: &str, layout: Layout) -> Option<()> {
+        let field_indent = struct_indent(self, outer, layout);
+        let colon = self.fields.last().is_some_and(|field| field.colon);
+        let mut field = render_field(key, value, &field_indent, colon, layout)?;
+
+        if let Some(last) = self.fields.last_mut() {
+            if !ends_line(&last.trailing) {
+                // A single-line struct, `{ x 1 }`, is opened up onto several lines
+                trim_trailing_whitespace(&mut last.trailing);
+                last.trailing.push(newline(layout.line_ending));
+            }
+            let first = &mut self.fields[0];
+            if self.closed && !first.leading.iter().any(is_newline) {
+                first.leading = std::iter::once(newline(layout.line_ending))
+                    .chain(whitespace(&field_indent))
+                    .collect();
+            }
+        } else if self.closed {
+            // The new field starts on the line after the opening brace
+            match self.trailing.iter().position(is_newline) {
+                Some(line_end) => {
+                    let head: Vec<Trivia> = self.trailing.drain(..=line_end).collect();
+                    field.leading.splice(0..0, head);
+                }
+                None => field.leading.insert(0, newline(layout.line_ending)),
+            }
+        }
+        if self.closed && !self.trailing.iter().any(is_newline) {
+            self.trailing = whitespace(outer).into_iter().collect();
+        }
+        self.fields.push(field);
+        Some(())
+    }
+}
+
+impl CstList {
+    /// Adds a new last item, copying the separators of the existing ones.
+    fn push(&mut self, value: CstValue, line_ending: LineEnding) {
+        if self.items.is_empty() {
+            self.items.push(CstItem {
+                leading: Vec::new(),
+                value,
+                trailing: Vec::new(),
+            });
+            return;
+        }
+
+        let leading = item_indent(self, self.items.len() - 1)
+            .and_then(|indent| whitespace(&indent))
+            .into_iter()
+            .collect();
+        let uses_commas = self.items.len() >= 2 && has_comma(&self.items[self.items.len() - 2]);
+        let last = last_mut(&mut self.items);
+        let trailing = if ends_line(&last.trailing) {
+            let mut trailing: Vec<Trivia> = last
+                .trailing
+                .iter()
+                .filter(|piece| piece.kind != TriviaKind::Comment)
+                .cloned()
+                .collect();
+            trailing.pop();
+            trim_trailing_whitespace(&mut trailing);
+            trailing.push(newline(line_ending));
+            if uses_commas && !has_comma(last) {
+                last.trailing.insert(0, comma());
+            }
+            trailing
+        } else {
+            let trailing = std::mem::take(&mut last.trailing);
+            let count = self.items.len();
+            last_mut(&mut self.items).trailing = if count >= 2 {
+                self.items[count - 2].trailing.clone()
+            } else {
+                whitespace(" ").into_iter().collect()
+            };
+            trailing
+        };
+        self.items.push(CstItem {
+            leading,
+            value,
+            trailing,
+        });
+    }
+}
+
+fn last_mut(items: &mut [CstItem]) -> &mut CstItem {
+    let last = items.len() - 1;
+    &mut items[last]
+}
+
+/// One step down from `value`, stepping through variants to their payload.
+fn child<'v>(mut value: &'v CstValue, segment: &str) -> Option<&'v CstValue> {
+    while let CstValue::Variant(variant) = value {
+        value = variant.payload.as_deref()?;
+    }
+    match value {
+        CstValue::Struct(fields) => Some(&fields.field(segment)?.value),
+        CstValue::Array(list) | CstValue::Tuple(list) => {
+            Some(&list.items.get(segment.parse::<usize>().ok()?)?.value)
+        }
+        CstValue::Scalar(_) | CstValue::Variant(_) => None,
+    }
+}
+
+fn wrong_kind(segments: &[&str], expected: &'static str) -> EditError {
+    EditError::WrongKind {
+        path: segments.join("."),
+        expected,
+    }
+}
+
+/// Writes `value` for a field at `field_indent`, keeping quotes around a
+/// string that replaces a quoted one and the base of a replaced integer.
+fn render_replacement(
+    old: &CstValue,
+    value: &Value,
+    field_indent: &str,
+    layout: Layout,
+) -> Option<CstValue> {
+    match (old, value) {
+        (CstValue::Scalar(old), Value::Str(s)) if old.is_quoted() => {
+            Some(CstValue::Scalar(CstScalar {
+                raw: writer::quote(s),
+                value: value.clone(),
+            }))
+        }
+        _ => keep_radix(old, value).or_else(|| render_value(value, field_indent, layout)),
+    }
+}
+
+/// `value` written like `old` when both are integers and `old` is not
+/// decimal, keeping the case of hex digits, or when both are bytes and `old`
+/// is written in hex.
+fn keep_radix(old: &CstValue, value: &Value) -> Option<CstValue> {
+    if let (CstValue::Scalar(old), Value::Bytes(bytes)) = (old, value)
+        && old.raw.starts_with("hex\"")
+    {
+        return Some(CstValue::Scalar(CstScalar {
+            raw: writer::hex_literal(bytes),
+            value: value.clone(),
+        }));
+    }
+    let (CstValue::Scalar(old), Some(n)) = (old, value.as_i128()) else {
+        return None;
+    };
+    let radix = old.radix().filter(|radix| *radix != Radix::Decimal)?;
+    let mut raw = radix.format(n);
+    if old.raw.bytes().any(|b| matches!(b, b'A'..=b'F'))
+        && let Some(prefix) = raw.find('x')
+    {
+        raw = format!(
+            "{}{}",
+            &raw[..=prefix],
+            raw[prefix + 1..].to_ascii_uppercase()
+        );
+    }
+    Some(CstValue::Scalar(CstScalar {
+        raw,
+        value: value.clone(),
+    }))
+}
+
+/// Rewrites the tuple `list` with the writer if a bare word such as `true` or
+/// `inf` is followed by another item on its line. In a tuple a bare word runs
+/// on to the end of the line, so the two would read back as one string.
+/// `outer` is the indentation of the line the tuple starts on.
+fn break_run_on_words(list: &mut CstList, outer: &str, layout: Layout) -> Option<()> {
+    let runs_on = list.items.windows(2).any(|pair| {
+        matches!(&pair[0].value, CstValue::Scalar(scalar) if crate::is_identifier(&scalar.raw))
+            && !ends_line(&pair[0].trailing)
+            && !pair[1].leading.iter().any(is_newline)
+    });
+    if runs_on {
+        let CstValue::Tuple(rendered) =
+            render_value(&Value::Tuple(list.to_values()), outer, layout)?
+        else {
+            return None;
+        };
+        *list = rendered;
+    }
+    Some(())
+}
+
+/// Writes `value` as an array or tuple item. Bare words run on to the end of
+/// a tuple, so strings are always quoted.
+fn render_item(value: &Value, line_indent: &str, layout: Layout) -> Option<CstValue> {
+    match value {
+        Value::Str(s) => Some(CstValue::Scalar(CstScalar {
+            raw: writer::quote(s),
+            value: value.clone(),
+        })),
+        _ => render_value(value, line_indent, layout),
+    }
+}
+
+fn render_value(value: &Value, line_indent: &str, layout: Layout) -> Option<CstValue> {
+    render_field("_", value, line_indent, false, layout).map(|field| field.value)
+}
+
+/// Writes a `key value` field whose first line is indented by `field_indent`.
+/// Returns `None` if the text does not read back cleanly as a single field.
+fn render_field(
+    key: &str,
+    value: &Value,
+    field_indent: &str,
+    colon: bool,
+    layout: Layout,
+) -> Option<CstField> {
+    let mut single = SeqMap::new();
+    let _ = single.insert(key.to_owned(), value.clone());
+    let options = WriteOptions {
+        indent: layout.indent,
+        key_colon: colon,
+        line_ending: layout.line_ending,
+        ..WriteOptions::default()
+    };
+    let text = writer::write_struct_with(&single, &options).ok()?;
+    let text = text.replace('\n', &format!("\n{field_indent}"));
+    let mut parser = Parser::new(text.trim_end());
+    let tree = parser.parse_cst();
+    if !parser.errors().is_empty() {
+        return None;
+    }
+    let [mut field] = <[CstField; 1]>::try_from(tree.root.fields).ok()?;
+    field.leading = whitespace(field_indent).into_iter().collect();
+    field.trailing = vec![newline(layout.line_ending)];
+    Some(field)
+}
+
+/// The indentation of the fields of `fields`, taken from the existing fields
+/// when possible. The root is the only struct without a closing brace.
+fn struct_indent(fields: &CstStruct, outer: &str, layout: Layout) -> String {
+    if !fields.closed {
+        return outer.to_owned();
+    }
+    (0..fields.fields.len())
+        .rev()
+        .find_map(|index| field_indent(fields, index))
+        .unwrap_or_else(|| nested_indent(outer, layout))
+}
+
+fn list_indent(list: &CstList, outer: &str, layout: Layout) -> String {
+    (0..list.items.len())
+        .rev()
+        .find_map(|index| item_indent(list, index))
+        .unwrap_or_else(|| nested_indent(outer, layout))
+}
+
+/// The indentation of field `index`, if it starts a line.
+fn field_indent(fields: &CstStruct, index: usize) -> Option<String> {
+    let previous = index.checked_sub(1).map(|i| &fields.fields[i].trailing[..]);
+    line_indent(&fields.fields[index].leading, previous)
+}
+
+/// The indentation of item `index`, if it starts a line.
+fn item_indent(list: &CstList, index: usize) -> Option<String> {
+    let previous = index.checked_sub(1).map(|i| &list.items[i].trailing[..]);
+    line_indent(&list.items[index].leading, previous)
+}
+
+/// The whitespace a node starts its line with, if it starts one. The line
+/// break is either in its own leading trivia or ends the previous node.
+fn line_indent(leading: &[Trivia], previous_trailing: Option<&[Trivia]>) -> Option<String> {
+    let start = match leading.iter().rposition(is_newline) {
+        Some(newline) => newline + 1,
+        None if previous_trailing.is_some_and(ends_line) => 0,
+        None => return None,
+    };
+    Some(
+        leading[start..]
+            .iter()
+            .filter(|piece| piece.kind == TriviaKind::Whitespace)
+            .map(|piece| piece.text.as_str())
+            .collect(),
+    )
+}
+
+fn nested_indent(outer: &str, layout: Layout) -> String {
+    let mut nested = outer.to_owned();
+    match layout.indent {
+        Indent::Spaces(width) => nested.extend(std::iter::repeat_n(' ', width)),
+        Indent::Tab => nested.push('\t'),
+    }
+    nested
+}
+
+/// Moves what should outlive a removed node from its leading trivia to the
+/// front of `next`: blank lines and any comments they separate from the node,
+/// and the end of the line before a first node.
+fn keep_leading(next: &mut Vec<Trivia>, leading: &[Trivia], first: bool) {
+    let mut kept = 0;
+    let mut line_start = 0;
+    for (index, piece) in leading.iter().enumerate() {
+        if piece.kind != TriviaKind::Newline {
+            continue;
+        }
+        let blank = leading[line_start..index]
+            .iter()
+            .all(|piece| piece.kind == TriviaKind::Whitespace);
+        if blank || (first && line_start == 0) {
+            kept = index + 1;
+        }
+        line_start = index + 1;
+    }
+    if kept > 0 && next.first().is_some_and(is_newline) {
+        // Two blank lines would meet where the node was
+        next.remove(0);
+    }
+    next.splice(0..0, leading[..kept].iter().cloned());
+}
+
+/// The indentation used by the first indented line of `text`, four spaces if none is.
+fn detect_indent(text: &str) -> Indent {
+    text.lines()
+        .find_map(|line| {
+            let content = line.trim_start_matches([' ', '\t']);
+            let indent = &line[..line.len() - content.len()];
+            if indent.is_empty() || content.is_empty() {
+                None
+            } else if indent.starts_with('\t') {
+                Some(Indent::Tab)
+            } else {
+                Some(Indent::Spaces(indent.len()))
+            }
+        })
+        .unwrap_or(Indent::Spaces(4))
+}
+
+fn ends_line(trivia: &[Trivia]) -> bool {
+    trivia.last().is_some_and(is_newline)
+}
+
+fn is_newline(piece: &Trivia) -> bool {
+    piece.kind == TriviaKind::Newline
+}
+
+fn trim_trailing_whitespace(trivia: &mut Vec<Trivia>) {
+    while trivia
+        .last()
+        .is_some_and(|piece| piece.kind == TriviaKind::Whitespace)
+    {
+        trivia.pop();
+    }
+}
+
+fn has_comma(item: &CstItem) -> bool {
+    item.trailing
+        .iter()
+        .any(|piece| piece.kind == TriviaKind::Comma)
+}
+
+fn comma() -> Trivia {
+    Trivia {
+        kind: TriviaKind::Comma,
+        text: ",".to_owned(),
+    }
+}
+
+fn newline(line_ending: LineEnding) -> Trivia {
+    Trivia {
+        kind: TriviaKind::Newline,
+        text: line_ending.as_str().to_owned(),
+    }
+}
+
+fn whitespace(text: &str) -> Option<Trivia> {
+    (!text.is_empty()).then(|| Trivia {
+        kind: TriviaKind::Whitespace,
+        text: text.to_owned(),
+    })
+}
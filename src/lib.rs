@@ -3,19 +3,89 @@
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
 
+use std::fmt;
+
 use seq_map::SeqMap;
 
+use crate::cst::SyntaxTree;
+use crate::datetime::DateTime;
+use crate::quantity::{Quantity, UnitRegistry};
+use crate::span::{Position, Span, SpanBuilder, SpanMark, StructSpans};
+
+pub mod access;
+pub mod cst;
+pub mod datetime;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod diagnostic;
+pub mod document;
+mod macros;
+pub mod path;
+pub mod quantity;
+#[cfg(feature = "serde")]
+pub mod ser;
+pub mod span;
+pub mod writer;
+
+#[cfg(feature = "serde")]
+pub use de::from_str;
+#[cfg(feature = "serde")]
+pub use ser::{to_string, to_string_with};
+
+pub use access::{FromValue, Lookup};
+pub use diagnostic::{DiagnosticOptions, render_diagnostics, render_diagnostics_with};
+pub use path::PathQuery;
+pub use writer::{
+    Indent, LineEnding, Separator, WriteError, WriteOptions, write_struct, write_struct_with,
+    write_value, write_value_with,
+};
+
 #[derive(Debug, Clone)]
 pub enum ErrorKind {
     ExpectedValueOnSameLine,
-    ExpectedNewlineAfterKeyValue,
+    /// Something other than a line break or comment followed a value.
+    ExpectedNewlineAfterKeyValue(char),
     UnterminatedBlock,
     UnterminatedString,
     InvalidUtf8InNumber,
     InvalidFloatFormat(String),
     InvalidIntegerFormat(String),
     UnexpectedEndOfInput,
+    /// A character that cannot start a key.
     UnexpectedCharacter(char),
+    /// A key that is already in the struct. The error is reported at the
+    /// repeated key and holds where the key first appeared.
+    DuplicateKey {
+        key: String,
+        first_line: usize,
+        first_column: usize,
+    },
+    /// Unquoted text with spaces in strict mode, which would otherwise be read
+    /// as one string running to the end of the line or tuple.
+    UnquotedString(String),
+    /// An unquoted word that is not an identifier, in strict mode.
+    InvalidBareWord(String),
+    /// A comma in an array or tuple that does not follow an item, as in
+    /// `[1,, 2]` or `(, 1)`.
+    UnexpectedComma,
+    /// An unknown or malformed escape sequence in a string, such as `\q`,
+    /// `\x80` or an unpaired surrogate in `\uD800`.
+    InvalidEscape(String),
+    /// Text that starts like a date but is not a valid RFC 3339 date or
+    /// date-time, such as `2024-02-30`.
+    InvalidDateTime(String),
+    /// A number with a duration unit that is negative, too large or finer
+    /// than a nanosecond, or with an unknown unit between others.
+    InvalidDuration(String),
+    /// A character in a `hex"..."` or `b64"..."` literal that is not a digit
+    /// of its encoding.
+    InvalidByteDigit {
+        found: char,
+        encoding: &'static str,
+    },
+    /// A byte literal that stops partway through a byte, such as `hex"abc"`.
+    /// Reported at the closing quote.
+    IncompleteBytes(&'static str),
 }
 
 #[derive(Debug, Clone)]
@@ -25,20 +95,95 @@ pub struct ParseError {
     pub kind: ErrorKind,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum Value {
+    /// `null`, for a setting that is explicitly unset.
+    #[default]
+    Null,
     Str(String),
+    /// Binary data, written `b64"aGk="` or `hex"6869"`.
+    Bytes(Vec<u8>),
+    /// An integer, read from any base. The base is not kept, see
+    /// [`Document`](document::Document) for editing that keeps it.
     Int(i64),
+    /// An integer outside the range of `i64`, such as a 64-bit hash above
+    /// `i64::MAX`. Integers that fit in an `i64` are always [`Value::Int`].
+    WideInt(i128),
     Num(f64),
     Bool(bool),
+    /// `2024-05-01` or `2024-05-01T12:30:00Z`, see [`datetime`].
+    DateTime(DateTime),
+    /// `250ms`, `1.5s` or `1h30m`, see [`datetime`].
+    Duration(std::time::Duration),
+    /// `64MB`, `80%` or `90deg`, see [`quantity`].
+    Quantity(Quantity),
     Variant(String, Option<Box<Value>>),
     Struct(Struct),
     Array(Vec<Value>),
     Tuple(Vec<Value>),
 }
 
+/// All errors reported while parsing a document, for use where one error value is expected.
+#[derive(Debug, Clone)]
+pub struct Errors(Vec<ParseError>);
+
+/// Why [`parse_file`] failed.
+#[derive(Debug)]
+pub enum FileError {
+    Io(std::io::Error),
+    Parse(Errors),
+}
+
 pub type Struct = SeqMap<String, Value>;
 
+/// Choices that change how the parser treats its input.
+#[derive(Debug, Clone, Default)]
+pub struct ParserOptions {
+    pub duplicate_keys: DuplicateKeys,
+    /// Turn off the conveniences that can hide typos: text with spaces must be
+    /// quoted instead of running to the end of the line or tuple, and bare
+    /// words must follow the identifier rules.
+    pub strict: bool,
+    /// The units that can follow a number to make a [`Value::Quantity`].
+    pub units: UnitRegistry,
+}
+
+/// What to do when a struct has the same key more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Report a [`ErrorKind::DuplicateKey`] error and keep the first value.
+    #[default]
+    Error,
+    /// Report a [`ErrorKind::DuplicateKey`] warning and keep the first value.
+    Warn,
+    /// Silently keep the first value.
+    FirstWins,
+    /// Silently keep the last value, at the position of the first.
+    LastWins,
+    /// Gather all values into a [`Value::Array`], in source order.
+    Collect,
+}
+
+/// Parses `input`, failing if any error was reported.
+///
+/// # Errors
+///
+/// Returns every error the parser reported.
+pub fn parse_str(input: &str) -> Result<Struct, Errors> {
+    Parser::new(input).finish()
+}
+
+/// Reads and parses the file at `path`, failing if any error was reported.
+///
+/// # Errors
+///
+/// Returns the I/O error if the file cannot be read as UTF-8 text, otherwise
+/// every error the parser reported.
+pub fn parse_file(path: impl AsRef<std::path::Path>) -> Result<Struct, FileError> {
+    let text = std::fs::read_to_string(path).map_err(FileError::Io)?;
+    parse_str(&text).map_err(FileError::Parse)
+}
+
 pub struct Parser<'a> {
     input: &'a [u8],
     len: usize,
@@ -46,33 +191,68 @@ pub struct Parser<'a> {
     line: usize,
     column: usize,
     errors: Vec<ParseError>,
+    warnings: Vec<ParseError>,
+    options: ParserOptions,
+    /// Where the keys of the structs being parsed start, innermost last.
+    keys: Vec<KeyRecord>,
+    spans: Option<SpanBuilder>,
+}
+
+#[derive(Clone, Copy)]
+struct KeyRecord {
+    line: usize,
+    column: usize,
+    /// Whether later values have been collected into an array.
+    collected: bool,
 }
 
 impl<'a> Parser<'a> {
     /// Create a new parser over the input string.
     #[must_use]
     pub const fn new(input: &'a str) -> Self {
+        Self::with_options(
+            input,
+            ParserOptions {
+                duplicate_keys: DuplicateKeys::Error,
+                strict: false,
+                units: UnitRegistry::new(),
+            },
+        )
+    }
+
+    /// Create a new parser over the input string using `options`.
+    #[must_use]
+    pub const fn with_options(input: &'a str, options: ParserOptions) -> Self {
         let bytes = input.as_bytes();
+        // A leading byte order mark is not part of the document
+        let pos = if let [0xEF, 0xBB, 0xBF, ..] = bytes {
+            3
+        } else {
+            0
+        };
         Parser {
             input: bytes,
             len: bytes.len(),
-            pos: 0,
+            pos,
             line: 1,
             column: 1,
             errors: Vec::new(),
+            warnings: Vec::new(),
+            options,
+            keys: Vec::new(),
+            spans: None,
         }
     }
 
     pub fn parse(&mut self) -> Struct {
         let mut root: Struct = SeqMap::new();
+        let base = self.keys.len();
         self.skip_ws_and_comments();
         while !self.is_eof() {
-            let key = self.parse_key();
-
-            // If we got an empty key, we hit an unexpected character
-            if key.is_empty() {
-                if let Some(b) = self.peek_byte() {
-                    let ch = b as char;
+            let key_start = self.position();
+            // Without a key, we hit an unexpected character
+            let Some(key) = self.parse_key() else {
+                if let Some(ch) = self.peek_char() {
                     self.errors.push(ParseError {
                         line: self.line,
                         column: self.column,
@@ -81,7 +261,7 @@ impl<'a> Parser<'a> {
                 }
                 self.synchronize();
                 continue;
-            }
+            };
 
             // Colon is optional - but must be *immediately* after key (no whitespace)
             if self.peek_byte() == Some(b':') {
@@ -91,48 +271,92 @@ impl<'a> Parser<'a> {
             self.skip_horizontal_ws();
 
             // Check if we have a value on the same line
-            if self.peek_byte() == Some(b'\n') || self.is_eof() {
+            if self.at_line_end() || self.is_eof() {
                 self.errors.push(ParseError {
                     line: self.line,
                     column: self.column,
                     kind: ErrorKind::ExpectedValueOnSameLine,
                 });
+                if let Some(spans) = &mut self.spans {
+                    spans.discard_field(false);
+                }
                 // Skip to next line to continue parsing
-                if self.peek_byte() == Some(b'\n') {
+                if self.at_line_end() {
                     self.next_byte();
                 }
                 continue;
             }
 
             let val = self.parse_field_value();
-
-            let _ = root.insert(key, val);
+            self.insert_field(&mut root, base, key, key_start, val);
             self.require_newline_or_eof();
             self.skip_ws_and_comments();
         }
+        self.keys.truncate(base);
         root
     }
 
+    /// Parses the whole input, returning the tree only if no errors were
+    /// reported. Use [`Parser::parse`] to keep whatever could be recovered.
+    ///
+    /// # Errors
+    ///
+    /// Returns every error the parser reported.
+    pub fn finish(mut self) -> Result<Struct, Errors> {
+        let root = self.parse();
+        if self.errors.is_empty() {
+            Ok(root)
+        } else {
+            Err(Errors(self.errors))
+        }
+    }
+
+    /// Parses like [`Parser::parse`], also returning the location of every key and value.
+    pub fn parse_spanned(&mut self) -> (Struct, StructSpans) {
+        self.spans = Some(SpanBuilder::default());
+        let root = self.parse();
+        let spans = self
+            .spans
+            .take()
+            .map(|spans| spans.finish(&root))
+            .unwrap_or_default();
+        (root, spans)
+    }
+
+    /// Parses into a lossless [`SyntaxTree`] that prints back to the exact input.
+    pub fn parse_cst(&mut self) -> SyntaxTree {
+        let (root, spans) = self.parse_spanned();
+        cst::build(self.slice_to_str(0, self.len), &root, &spans)
+    }
+
     #[must_use]
     pub fn errors(&self) -> &[ParseError] {
         &self.errors
     }
 
+    /// Problems that did not stop the input from being accepted, such as
+    /// duplicate keys under [`DuplicateKeys::Warn`].
+    #[must_use]
+    pub fn warnings(&self) -> &[ParseError] {
+        &self.warnings
+    }
+
     fn parse_struct(&mut self) -> Struct {
         let mut map = SeqMap::new();
+        let base = self.keys.len();
         self.skip_ws_and_comments();
 
         while let Some(b) = self.peek_byte() {
             if b == b'}' {
                 self.next_byte();
+                self.keys.truncate(base);
                 return map;
             }
-            let key = self.parse_key();
-
-            // If we got an empty key, we hit an unexpected character
-            if key.is_empty() {
-                if let Some(b) = self.peek_byte() {
-                    let ch = b as char;
+            self.skip_ws_and_comments();
+            let key_start = self.position();
+            // Without a key, we hit an unexpected character
+            let Some(key) = self.parse_key() else {
+                if let Some(ch) = self.peek_char() {
                     self.errors.push(ParseError {
                         line: self.line,
                         column: self.column,
@@ -141,7 +365,7 @@ impl<'a> Parser<'a> {
                 }
                 self.synchronize();
                 continue;
-            }
+            };
 
             // Code is repeated here for performance reasons
             // Colon is optional - but must be *immediately* after key (no whitespace)
@@ -151,24 +375,28 @@ impl<'a> Parser<'a> {
 
             self.skip_horizontal_ws();
 
-            if self.peek_byte() == Some(b'\n') || self.is_eof() {
+            if self.at_line_end() || self.is_eof() {
                 self.errors.push(ParseError {
                     line: self.line,
                     column: self.column,
                     kind: ErrorKind::ExpectedValueOnSameLine,
                 });
+                if let Some(spans) = &mut self.spans {
+                    spans.discard_field(false);
+                }
                 // Skip to next line to continue parsing
-                if self.peek_byte() == Some(b'\n') {
+                if self.at_line_end() {
                     self.next_byte();
                 }
                 continue;
             }
 
             let val = self.parse_field_value();
-            let _ = map.insert(key, val);
+            self.insert_field(&mut map, base, key, key_start, val);
             self.require_newline_or_eof();
             self.skip_ws_and_comments();
         }
+        self.keys.truncate(base);
 
         self.errors.push(ParseError {
             line: self.line,
@@ -179,6 +407,65 @@ impl<'a> Parser<'a> {
         map
     }
 
+    /// Adds a field to `map`, whose keys are recorded from `base` on, applying
+    /// the duplicate key policy if the key is already there.
+    fn insert_field(
+        &mut self,
+        map: &mut Struct,
+        base: usize,
+        key: String,
+        key_start: Position,
+        value: Value,
+    ) {
+        let Some(index) = map.get_index(&key) else {
+            // The key was just looked up, so this insert can not fail
+            let _ = map.insert(key, value);
+            self.keys.push(KeyRecord {
+                line: key_start.line,
+                column: key_start.column,
+                collected: false,
+            });
+            return;
+        };
+
+        // Spans keep pointing at the first occurrence
+        if let Some(spans) = &mut self.spans {
+            spans.discard_field(true);
+        }
+        let first = self.keys[base + index];
+        let duplicate = || ParseError {
+            line: key_start.line,
+            column: key_start.column,
+            kind: ErrorKind::DuplicateKey {
+                key: key.clone(),
+                first_line: first.line,
+                first_column: first.column,
+            },
+        };
+        match self.options.duplicate_keys {
+            DuplicateKeys::Error => self.errors.push(duplicate()),
+            DuplicateKeys::Warn => self.warnings.push(duplicate()),
+            DuplicateKeys::FirstWins => {}
+            DuplicateKeys::LastWins => {
+                if let Some(existing) = map.get_mut(&key) {
+                    *existing = value;
+                }
+            }
+            DuplicateKeys::Collect => {
+                if let Some(existing) = map.get_mut(&key) {
+                    if !first.collected {
+                        let old = std::mem::replace(existing, Value::Array(Vec::new()));
+                        *existing = Value::Array(vec![old]);
+                        self.keys[base + index].collected = true;
+                    }
+                    if let Value::Array(items) = existing {
+                        items.push(value);
+                    }
+                }
+            }
+        }
+    }
+
     fn parse_array(&mut self) -> Vec<Value> {
         let mut array = Vec::new();
         self.skip_ws_and_comments();
@@ -191,6 +478,7 @@ impl<'a> Parser<'a> {
 
         loop {
             self.skip_ws_and_comments();
+            self.skip_extra_commas();
 
             // End of array
             if self.peek_byte() == Some(b']') {
@@ -212,6 +500,7 @@ impl<'a> Parser<'a> {
             array.push(value);
 
             self.skip_ws_and_comments();
+            self.skip_comma();
 
             match self.peek_byte() {
                 Some(b']') => {
@@ -220,8 +509,7 @@ impl<'a> Parser<'a> {
                     return array;
                 }
                 Some(_) => {
-                    // Whitespace-separated item, continue to next iteration
-                    // No error needed since commas are optional
+                    // Next item, separated by whitespace or a comma
                 }
                 None => {
                     self.errors.push(ParseError {
@@ -235,22 +523,53 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_key(&mut self) -> String {
+    /// Parses a key, returning `None` if there is no key at the current
+    /// position. A quoted key may be empty (`""`).
+    fn parse_key(&mut self) -> Option<String> {
         self.skip_ws_and_comments();
-        self.parse_identifier_or_string()
+        let start = self.position();
+        let quoted = self.peek_byte() == Some(b'"');
+        let key = self.parse_identifier_or_string();
+        if key.is_empty() && !quoted {
+            return None;
+        }
+        let end = self.position();
+        if let Some(spans) = &mut self.spans {
+            spans.push_key(Span { start, end });
+        }
+        Some(key)
     }
 
     fn parse_value(&mut self) -> Value {
         self.skip_ws_and_comments();
+        if self.spans.is_some() {
+            let start = self.position();
+            let mark = self.span_mark();
+            let value = self.parse_value_inner();
+            self.close_span(start, mark, &value);
+            return value;
+        }
+        self.parse_value_inner()
+    }
+
+    /// Parses the value at the current position, which must not be whitespace.
+    #[inline]
+    fn parse_value_inner(&mut self) -> Value {
         match self.peek_byte() {
             Some(b'(') => {
                 // parenthesized tuple
                 self.parse_tuple()
             }
             Some(b'"') => {
-                let s = self.parse_string();
+                let s = if self.input[self.pos..].starts_with(b"\"\"\"") {
+                    self.parse_multiline_string()
+                } else {
+                    self.parse_string()
+                };
                 Value::Str(s)
             }
+            Some(b'r') if self.at_raw_string() => Value::Str(self.parse_raw_string()),
+            Some(b'b' | b'h') if self.at_bytes() => Value::Bytes(self.parse_bytes()),
             Some(b'{') => {
                 self.next_byte();
                 Value::Struct(self.parse_struct())
@@ -266,6 +585,8 @@ impl<'a> Parser<'a> {
 
                 // Check for optional payload: (tuple) {object} [array]
                 // NO whitespace allowed between variant name and payload
+                let payload_start = self.position();
+                let payload_mark = self.span_mark();
                 let payload = match self.peek_byte() {
                     Some(b'(') => {
                         // Tuple payload: :variant(a, b, c)
@@ -283,19 +604,21 @@ impl<'a> Parser<'a> {
                     }
                     _ => None,
                 };
+                if let Some(payload) = &payload {
+                    self.close_span(payload_start, payload_mark, payload);
+                }
 
                 Value::Variant(id, payload)
             }
-            Some(b'-' | b'0'..=b'9') => self.parse_numeric(),
+            Some(b'0'..=b'9') => self.parse_numeric(),
+            Some(b'-' | b'+' | b'.') if self.at_number() => self.parse_numeric(),
             Some(_) => {
+                let start = self.position();
                 let id = self.parse_identifier_or_string();
-                if id == "true" {
-                    Value::Bool(true)
-                } else if id == "false" {
-                    Value::Bool(false)
-                } else {
-                    Value::Str(id)
+                if self.options.strict && !id.is_empty() {
+                    self.check_bare_text(start, &id);
                 }
+                keyword(&id).unwrap_or(Value::Str(id))
             }
             None => {
                 self.errors.push(ParseError {
@@ -310,26 +633,36 @@ impl<'a> Parser<'a> {
 
     fn parse_field_value(&mut self) -> Value {
         self.skip_horizontal_ws();
+        let start = self.position();
+        let mark = self.span_mark();
 
         // If parenthesized tuple, parse it
         if self.peek_byte() == Some(b'(') {
-            return self.parse_tuple();
+            let value = self.parse_tuple();
+            self.close_span(start, mark, &value);
+            return value;
         }
 
         let start_pos = self.pos;
+        let numeric = self.at_number();
+        let errors = self.errors.len();
         // parse first token/value
         let first = self.parse_value();
         self.skip_horizontal_ws();
 
         match self.peek_byte() {
-            Some(b'\n' | b'#' | b'}') | None => {
+            Some(b'\n' | b'\r' | b'#' | b'}') | None => {
                 // single value
                 first
             }
             Some(_) => {
+                if numeric {
+                    // The line is read as a string, which covers the number
+                    self.errors.truncate(errors);
+                }
                 // Move to line end or comment
                 while let Some(b) = self.peek_byte() {
-                    if b == b'\n' || b == b'#' {
+                    if b == b'\n' || b == b'\r' || b == b'#' {
                         break;
                     }
                     self.next_byte();
@@ -341,7 +674,12 @@ impl<'a> Parser<'a> {
                     self.pos = start_pos;
                     first
                 } else {
-                    Value::Str(trimmed.to_owned())
+                    if self.options.strict {
+                        self.check_bare_text(start, trimmed);
+                    }
+                    let value = Value::Str(trimmed.to_owned());
+                    self.replace_span_with_scalar(start, mark, trimmed.len());
+                    value
                 }
             }
         }
@@ -354,6 +692,7 @@ impl<'a> Parser<'a> {
 
         loop {
             self.skip_ws_and_comments();
+            self.skip_extra_commas();
 
             if self.peek_byte() == Some(b')') {
                 self.next_byte();
@@ -369,52 +708,22 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            let v = match self.peek_byte() {
-                Some(b'"' | b'{' | b'[' | b'(' | b'-' | b'0'..=b'9' | b':') => self.parse_value(),
-                Some(_) => {
-                    // collect until comma, ')' or end-of-input/comment/newline
-                    let start = self.pos;
-                    while let Some(b) = self.peek_byte() {
-                        if b == b')' || b == b'#' || b == b'\n' {
-                            break;
-                        }
-                        self.next_byte();
-                    }
-                    let trimmed = self.slice_to_str(start, self.pos).trim();
-                    if trimmed.is_empty() {
-                        // fallback to parse_value to generate an error or value
-                        self.parse_value()
-                    } else if trimmed == "true" {
-                        Value::Bool(true)
-                    } else if trimmed == "false" {
-                        Value::Bool(false)
-                    } else {
-                        Value::Str(trimmed.to_owned())
-                    }
-                }
-                None => {
-                    self.errors.push(ParseError {
-                        line: self.line,
-                        column: self.column,
-                        kind: ErrorKind::UnexpectedEndOfInput,
-                    });
-                    break;
-                }
-            };
+            let v = self.parse_tuple_item();
             items.push(v);
 
             self.skip_ws_and_comments();
+            self.skip_comma();
 
             match self.peek_byte() {
                 Some(b')') => {
                     self.next_byte();
                     break;
                 }
-                Some(b'#' | b'\n') => {
+                Some(b'#' | b'\n' | b'\r') => {
                     // allow comments/newlines inside tuple, keep looping
                 }
                 Some(_) => {
-                    // Whitespace-separated item, continue to next iteration
+                    // Next item, separated by whitespace or a comma
                 }
                 None => break,
             }
@@ -423,6 +732,51 @@ impl<'a> Parser<'a> {
         Value::Tuple(items)
     }
 
+    /// Parses a single tuple item. Bare words run until a comma or the end of the
+    /// tuple or line.
+    fn parse_tuple_item(&mut self) -> Value {
+        match self.peek_byte() {
+            Some(b'"' | b'{' | b'[' | b'(' | b'0'..=b'9' | b':') => self.parse_value(),
+            Some(b'-' | b'+' | b'.') if self.at_number() => self.parse_value(),
+            Some(b'r') if self.at_raw_string() => self.parse_value(),
+            Some(b'b' | b'h') if self.at_bytes() => self.parse_value(),
+            Some(_) => {
+                // collect until comma, ')' or end-of-input/comment/newline
+                let start_position = self.position();
+                let mark = self.span_mark();
+                let start = self.pos;
+                while let Some(b) = self.peek_byte() {
+                    if matches!(b, b',' | b')' | b'#' | b'\n' | b'\r') {
+                        break;
+                    }
+                    self.next_byte();
+                }
+                let trimmed = self.slice_to_str(start, self.pos).trim();
+                if trimmed.is_empty() {
+                    // fallback to parse_value to generate an error or value
+                    return self.parse_value();
+                }
+                if self.options.strict {
+                    self.check_bare_text(start_position, trimmed);
+                }
+                let len = trimmed.len();
+                let value = keyword(trimmed).unwrap_or_else(|| Value::Str(trimmed.to_owned()));
+                self.replace_span_with_scalar(start_position, mark, len);
+                value
+            }
+            None => {
+                self.errors.push(ParseError {
+                    line: self.line,
+                    column: self.column,
+                    kind: ErrorKind::UnexpectedEndOfInput,
+                });
+                let value = Value::Str(String::new());
+                self.close_span(self.position(), self.span_mark(), &value);
+                value
+            }
+        }
+    }
+
     #[inline]
     fn parse_identifier_or_string(&mut self) -> String {
         self.skip_ws_and_comments();
@@ -436,7 +790,7 @@ impl<'a> Parser<'a> {
                 // Fast delimiter check
                 match b {
                     b' ' | b'\t' | b'\n' | b'\r' | b'{' | b'}' | b'[' | b']' | b':' | b'('
-                    | b')' => break,
+                    | b')' | b',' => break,
                     _ => {
                         self.pos += 1;
                         self.column += 1;
@@ -448,7 +802,7 @@ impl<'a> Parser<'a> {
     }
 
     #[inline]
-    fn slice_to_str(&self, start: usize, end: usize) -> &str {
+    fn slice_to_str(&self, start: usize, end: usize) -> &'a str {
         debug_assert!(start <= end && end <= self.len);
         // SAFETY: input originates from a valid UTF-8 source string
         unsafe { std::str::from_utf8_unchecked(&self.input[start..end]) }
@@ -460,119 +814,517 @@ impl<'a> Parser<'a> {
         while self.pos < self.len {
             // SAFETY: We just checked pos < len
             let b = unsafe { *self.input.get_unchecked(self.pos) };
-            match b {
-                b' ' | b'\t' | b'\n' | b'\r' | b'{' | b'}' | b'[' | b']' | b')' | b'(' | b':' => {
-                    break;
-                }
-                _ => {
-                    self.pos += 1;
-                    self.column += 1;
-                }
+            if ends_variant_name(b) {
+                break;
             }
+            self.pos += 1;
+            self.column += 1;
         }
         // SAFETY: start and pos are valid indices
         self.slice_to_str(start, self.pos).to_owned()
     }
 
     fn parse_string(&mut self) -> String {
+        let (line, column) = (self.line, self.column);
         self.next_byte();
         let mut raw = Vec::with_capacity(16);
         while let Some(b) = self.next_byte() {
             match b {
                 b'"' => {
-                    // SAFETY: raw is built from the original UTF-8 input plus ASCII escapes
+                    // SAFETY: raw is built from the original UTF-8 input plus encoded escapes
                     return unsafe { String::from_utf8_unchecked(raw) };
                 }
-                b'\\' => {
-                    if let Some(esc) = self.next_byte() {
-                        match esc {
-                            b'n' => raw.push(b'\n'),
-                            b't' => raw.push(b'\t'),
-                            b'r' => raw.push(b'\r'),
-                            b'"' => raw.push(b'"'),
-                            b'\\' => raw.push(b'\\'),
-                            other => raw.push(other),
-                        }
+                b'\\' => self.parse_escape(&mut raw),
+                // Line breaks in strings read as `\n` whatever the file uses
+                b'\r' => {
+                    if self.peek_byte() != Some(b'\n') {
+                        raw.push(b'\n');
                     }
                 }
                 other => raw.push(other),
             }
         }
-        // unterminated string
+        // unterminated string, reported where it starts
         self.errors.push(ParseError {
-            line: self.line,
-            column: self.column,
+            line,
+            column,
             kind: ErrorKind::UnterminatedString,
         });
         // SAFETY: partial string still contains only bytes from the original UTF-8 input
         unsafe { String::from_utf8_unchecked(raw) }
     }
 
+    /// Parses a `"""` string. A line break right after the opening quotes and
+    /// the line holding the closing quotes are left out, and so is the
+    /// indentation all lines share. Escapes work as in other strings.
+    fn parse_multiline_string(&mut self) -> String {
+        let (line, column) = (self.line, self.column);
+        for _ in 0..3 {
+            self.next_byte();
+        }
+
+        // Find the closing quotes first, as the shared indentation depends on every line
+        let mut end = self.pos;
+        let closed = loop {
+            match self.input.get(end) {
+                None => break false,
+                Some(b'\\') => end += 2,
+                Some(b'"') if self.input[end..].starts_with(b"\"\"\"") => break true,
+                Some(_) => end += 1,
+            }
+        };
+        let end = end.min(self.len);
+        if !closed {
+            self.errors.push(ParseError {
+                line,
+                column,
+                kind: ErrorKind::UnterminatedString,
+            });
+        }
+
+        let body = self.slice_to_str(self.pos, end);
+        let is_blank = |text: &str| text.trim_start_matches([' ', '\t']).is_empty();
+        let mut start = 0;
+        let mut content_end = body.len();
+        let mut closing_indent = None;
+        if let Some(first_break) = body.find(['\n', '\r']) {
+            if is_blank(&body[..first_break]) {
+                start = first_break + line_break_len(&body[first_break..]);
+            }
+            let last_break = body.rfind(['\n', '\r']).unwrap_or(first_break);
+            let closing_line = &body[last_break + 1..];
+            if is_blank(closing_line) {
+                closing_indent = Some(closing_line.len());
+                content_end =
+                    if body[..last_break].ends_with('\r') && body[last_break..].starts_with('\n') {
+                        last_break - 1
+                    } else {
+                        last_break
+                    };
+            }
+        }
+        let content_end = content_end.max(start);
+        let indent = body[start..content_end]
+            .split(['\n', '\r'])
+            .filter(|line| !is_blank(line))
+            .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+            .chain(closing_indent)
+            .min()
+            .unwrap_or(0);
+
+        let (start, content_end) = (self.pos + start, self.pos + content_end);
+        while self.pos < start {
+            self.next_byte();
+        }
+        let mut raw = Vec::with_capacity(content_end - start);
+        let mut line_start = true;
+        while self.pos < content_end {
+            if line_start {
+                line_start = false;
+                let mut skipped = 0;
+                while skipped < indent
+                    && self.pos < content_end
+                    && matches!(self.peek_byte(), Some(b' ' | b'\t'))
+                {
+                    self.next_byte();
+                    skipped += 1;
+                }
+                continue;
+            }
+            let Some(b) = self.next_byte() else {
+                break;
+            };
+            match b {
+                b'\\' => self.parse_escape(&mut raw),
+                b'\n' | b'\r' => {
+                    if b == b'\r' && self.peek_byte() == Some(b'\n') {
+                        self.next_byte();
+                    }
+                    raw.push(b'\n');
+                    line_start = true;
+                }
+                other => raw.push(other),
+            }
+        }
+        while self.pos < end {
+            self.next_byte();
+        }
+        if closed {
+            for _ in 0..3 {
+                self.next_byte();
+            }
+        }
+        // SAFETY: raw is built from the original UTF-8 input plus encoded escapes,
+        // split only at ASCII bytes
+        unsafe { String::from_utf8_unchecked(raw) }
+    }
+
+    /// Whether a raw string, `r"..."` or `r#"..."#`, starts here.
+    fn at_raw_string(&self) -> bool {
+        let rest = &self.input[self.pos..];
+        rest.first() == Some(&b'r') && rest[1..].iter().find(|&&b| b != b'#') == Some(&b'"')
+    }
+
+    /// Parses a raw string, where backslashes are literal. It ends at a `"`
+    /// followed by as many `#` as there are after the opening `r`.
+    fn parse_raw_string(&mut self) -> String {
+        let (line, column) = (self.line, self.column);
+        self.next_byte();
+        let mut hashes = 0;
+        while self.peek_byte() == Some(b'#') {
+            self.next_byte();
+            hashes += 1;
+        }
+        self.next_byte();
+        let start = self.pos;
+        while let Some(b) = self.next_byte() {
+            if b == b'"'
+                && self
+                    .input
+                    .get(self.pos..self.pos + hashes)
+                    .is_some_and(|tail| tail.iter().all(|&b| b == b'#'))
+            {
+                let text = normalize_line_breaks(self.slice_to_str(start, self.pos - 1));
+                for _ in 0..hashes {
+                    self.next_byte();
+                }
+                return text;
+            }
+        }
+        self.errors.push(ParseError {
+            line,
+            column,
+            kind: ErrorKind::UnterminatedString,
+        });
+        normalize_line_breaks(self.slice_to_str(start, self.pos))
+    }
+
+    /// Whether a `b64"..."` or `hex"..."` byte literal starts here.
+    pub(crate) fn at_bytes(&self) -> bool {
+        let rest = &self.input[self.pos..];
+        rest.starts_with(b"b64\"") || rest.starts_with(b"hex\"")
+    }
+
+    /// Parses a `b64"..."` or `hex"..."` literal into the bytes it encodes.
+    /// Whitespace between digits is ignored, so long data can be split over
+    /// lines. Only the first invalid digit is reported.
+    fn parse_bytes(&mut self) -> Vec<u8> {
+        let (line, column) = (self.line, self.column);
+        let hex = self.peek_byte() == Some(b'h');
+        let (encoding, width) = if hex { ("hex", 4) } else { ("base64", 6) };
+        for _ in 0..4 {
+            self.next_byte();
+        }
+        let mut bytes = Vec::new();
+        let (mut buffer, mut bits) = (0_u32, 0);
+        let (mut digits, mut padding) = (0_usize, 0_usize);
+        let mut valid = true;
+        loop {
+            let (digit_line, digit_column) = (self.line, self.column);
+            let found = self.peek_char();
+            match self.next_byte() {
+                None => {
+                    self.errors.push(ParseError {
+                        line,
+                        column,
+                        kind: ErrorKind::UnterminatedString,
+                    });
+                    return bytes;
+                }
+                Some(b'"') => break,
+                // Whitespace is skipped, and so is anything after an invalid digit
+                Some(b) if !valid || matches!(b, b' ' | b'\t' | b'\n' | b'\r') => {}
+                Some(b'=') if !hex && digits > 0 && padding < 2 => padding += 1,
+                Some(b) => {
+                    let value = if hex {
+                        char::from(b).to_digit(16)
+                    } else {
+                        base64_digit(b).filter(|_| padding == 0)
+                    };
+                    if let Some(value) = value {
+                        digits += 1;
+                        buffer = (buffer << width) | value;
+                        bits += width;
+                        if bits >= 8 {
+                            bits -= 8;
+                            bytes.push(u8::try_from(buffer >> bits).unwrap_or(0));
+                            buffer &= (1 << bits) - 1;
+                        }
+                    } else {
+                        valid = false;
+                        self.errors.push(ParseError {
+                            line: digit_line,
+                            column: digit_column,
+                            kind: ErrorKind::InvalidByteDigit {
+                                found: found.unwrap_or('\u{fffd}'),
+                                encoding,
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        let complete = if hex {
+            digits % 2 == 0
+        } else {
+            digits % 4 != 1 && (padding == 0 || (digits + padding) % 4 == 0)
+        };
+        if valid && !complete {
+            self.errors.push(ParseError {
+                line: self.line,
+                column: self.column - 1,
+                kind: ErrorKind::IncompleteBytes(encoding),
+            });
+        }
+        bytes
+    }
+
+    /// Reads the escape sequence following a `\` in a string onto `raw`.
+    fn parse_escape(&mut self, raw: &mut Vec<u8>) {
+        let (line, column, start) = (self.line, self.column - 1, self.pos - 1);
+        let escaped = self.peek_char();
+        let Some(esc) = self.next_byte() else {
+            return;
+        };
+        let ch = match esc {
+            b'n' => Some('\n'),
+            b't' => Some('\t'),
+            b'r' => Some('\r'),
+            b'0' => Some('\0'),
+            b'"' => Some('"'),
+            b'\\' => Some('\\'),
+            b'x' => self
+                .parse_hex(2)
+                .filter(|&code| code < 0x80)
+                .and_then(char::from_u32),
+            b'u' => self.parse_unicode_escape(),
+            b'\n' | b'\r' => {
+                // A line continuation skips the line break and the next line's indentation
+                while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek_byte() {
+                    self.next_byte();
+                }
+                return;
+            }
+            _ => None,
+        };
+        if let Some(ch) = ch {
+            raw.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            return;
+        }
+
+        let end = if matches!(esc, b'x' | b'u') {
+            self.pos
+        } else {
+            // Keep the unknown character, which may be longer than a byte
+            raw.push(esc);
+            start + 1 + escaped.map_or(0, char::len_utf8)
+        };
+        self.errors.push(ParseError {
+            line,
+            column,
+            kind: ErrorKind::InvalidEscape(self.slice_to_str(start, end).to_owned()),
+        });
+    }
+
+    /// Reads the code point of a `\u{...}` or `\uXXXX` escape, where a UTF-16
+    /// high surrogate must be followed by a low one, as in `\uD83D\uDE00`.
+    fn parse_unicode_escape(&mut self) -> Option<char> {
+        if self.peek_byte() == Some(b'{') {
+            self.next_byte();
+            let (code, digits) = self.parse_hex_digits(6);
+            if self.peek_byte() != Some(b'}') {
+                return None;
+            }
+            self.next_byte();
+            return char::from_u32(code).filter(|_| digits > 0);
+        }
+        let high = self.parse_hex(4)?;
+        if !(0xD800..0xDC00).contains(&high) {
+            // Lone low surrogates are rejected by `from_u32`
+            return char::from_u32(high);
+        }
+        if self.peek_byte() != Some(b'\\') || self.input.get(self.pos + 1) != Some(&b'u') {
+            return None;
+        }
+        self.next_byte();
+        self.next_byte();
+        let low = self
+            .parse_hex(4)
+            .filter(|low| (0xDC00..0xE000).contains(low))?;
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+
+    /// Reads exactly `len` hex digits.
+    fn parse_hex(&mut self, len: usize) -> Option<u32> {
+        let (code, digits) = self.parse_hex_digits(len);
+        (digits == len).then_some(code)
+    }
+
+    /// Reads up to `max` hex digits, returning their value and how many there were.
+    fn parse_hex_digits(&mut self, max: usize) -> (u32, usize) {
+        let mut code = 0;
+        let mut digits = 0;
+        while digits < max
+            && let Some(digit) = self.peek_byte().and_then(|b| char::from(b).to_digit(16))
+        {
+            code = code * 16 + digit;
+            digits += 1;
+            self.next_byte();
+        }
+        (code, digits)
+    }
+
+    /// Whether a number starts here: a digit, or a `.` before one, after an
+    /// optional sign, or a signed `inf` or `nan`.
+    fn at_number(&self) -> bool {
+        let rest = &self.input[self.pos..];
+        let unsigned = match rest {
+            [b'+' | b'-', unsigned @ ..] => unsigned,
+            _ => rest,
+        };
+        match unsigned {
+            [b'0'..=b'9', ..] | [b'.', b'0'..=b'9', ..] => true,
+            [b'i', b'n', b'f', after @ ..] | [b'n', b'a', b'n', after @ ..] => {
+                unsigned.len() < rest.len()
+                    && !after
+                        .first()
+                        .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_')
+            }
+            _ => false,
+        }
+    }
+
+    /// Parses a number, date or duration. A token that fails to parse and
+    /// was not clearly meant as one, such as `1.2.3` or `5kg` with an unknown
+    /// unit, is read as text, wherever it appears.
     #[inline]
     fn parse_numeric(&mut self) -> Value {
+        let start_position = self.position();
         let start = self.pos;
-        // optional sign
-        if self.peek_byte() == Some(b'-') {
+        let errors = self.errors.len();
+        let value = self.parse_numeric_token();
+        let malformed = self.errors[errors..]
+            .iter()
+            .any(|error| is_malformed_number(&error.kind));
+        let token = self.slice_to_str(start, self.pos);
+        if !malformed || meant_as_number(token) {
+            return value;
+        }
+        let token = token.to_owned();
+        self.errors.truncate(errors);
+        if self.options.strict {
+            self.check_bare_text(start_position, &token);
+        }
+        Value::Str(token)
+    }
+
+    #[inline]
+    fn parse_numeric_token(&mut self) -> Value {
+        let start = self.pos;
+        if self.at_date() {
+            return self.parse_datetime();
+        }
+        if matches!(self.peek_byte(), Some(b'+' | b'-')) {
             self.pos += 1;
             self.column += 1;
         }
-        // digits before decimal
+        let decimal = !matches!(
+            self.input.get(self.pos..self.pos + 2),
+            Some(b"0x" | b"0X" | b"0o" | b"0O" | b"0b" | b"0B")
+        );
+        // Take the whole token, so that a malformed number is reported as one
+        let mut previous = 0;
+        let mut plain = true;
         while self.pos < self.len {
             // SAFETY: We just checked pos < len
             let b = unsafe { *self.input.get_unchecked(self.pos) };
-            if !b.is_ascii_digit() {
+            let exponent_sign =
+                decimal && matches!(b, b'+' | b'-') && matches!(previous, b'e' | b'E');
+            if !(b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'%') || exponent_sign) {
                 break;
             }
+            plain &= b.is_ascii_digit();
+            previous = b;
             self.pos += 1;
             self.column += 1;
         }
-        let is_float = if self.peek_byte() == Some(b'.') {
-            // consume '.' and fraction
+        let text = self.slice_to_str(start, self.pos);
+        // Plain integers are the most common numbers and need no further checks
+        if plain && let Ok(n) = text.parse() {
+            return Value::Int(n);
+        }
+        match parse_number(text, &self.options.units) {
+            Ok(value) => value,
+            Err(kind) => {
+                let value = match kind {
+                    ErrorKind::InvalidFloatFormat(_) => Value::Num(0.0),
+                    ErrorKind::InvalidDuration(_) => Value::Duration(std::time::Duration::ZERO),
+                    _ => Value::Int(0),
+                };
+                self.errors.push(ParseError {
+                    line: self.line,
+                    column: self.column,
+                    kind,
+                });
+                value
+            }
+        }
+    }
+
+    /// Whether a date starts here: four digits and a `-`.
+    #[inline]
+    fn at_date(&self) -> bool {
+        matches!(
+            self.input.get(self.pos..self.pos + 5),
+            Some([b'0'..=b'9', b'0'..=b'9', b'0'..=b'9', b'0'..=b'9', b'-'])
+        )
+    }
+
+    #[cold]
+    fn parse_datetime(&mut self) -> Value {
+        let start = self.pos;
+        while let Some(b) = self.peek_byte() {
+            if !(b.is_ascii_digit()
+                || matches!(b, b'-' | b':' | b'.' | b'+' | b'T' | b't' | b'Z' | b'z'))
+            {
+                break;
+            }
             self.pos += 1;
             self.column += 1;
-            while self.pos < self.len {
-                // SAFETY: We just checked pos < len
-                let b = unsafe { *self.input.get_unchecked(self.pos) };
-                if !b.is_ascii_digit() {
-                    break;
-                }
-                self.pos += 1;
-                self.column += 1;
-            }
-            true
-        } else {
-            false
-        };
-        // SAFETY: start..pos are valid indices within input
-        let slice = unsafe { self.input.get_unchecked(start..self.pos) };
-        let Ok(s) = std::str::from_utf8(slice) else {
-            self.errors.push(ParseError {
-                line: self.line,
-                column: self.column,
-                kind: ErrorKind::InvalidUtf8InNumber,
-            });
-            return Value::Int(0);
-        };
-        if is_float {
-            if let Ok(n) = s.parse::<f64>() {
-                Value::Num(n)
-            } else {
+        }
+        let text = self.slice_to_str(start, self.pos);
+        text.parse().map_or_else(
+            |_| {
                 self.errors.push(ParseError {
                     line: self.line,
                     column: self.column,
-                    kind: ErrorKind::InvalidFloatFormat(s.to_string()),
+                    kind: ErrorKind::InvalidDateTime(text.to_owned()),
                 });
-                Value::Num(0.0)
-            }
-        } else if let Ok(n) = s.parse::<i64>() {
-            Value::Int(n)
-        } else {
+                Value::Str(text.to_owned())
+            },
+            Value::DateTime,
+        )
+    }
+
+    /// Consumes the optional comma after an array or tuple item.
+    #[inline]
+    fn skip_comma(&mut self) {
+        if self.peek_byte() == Some(b',') {
+            self.next_byte();
+            self.skip_ws_and_comments();
+        }
+    }
+
+    /// Reports commas that do not follow an item, like the second one in `[1,, 2]`.
+    fn skip_extra_commas(&mut self) {
+        while self.peek_byte() == Some(b',') {
             self.errors.push(ParseError {
                 line: self.line,
                 column: self.column,
-                kind: ErrorKind::InvalidIntegerFormat(s.to_string()),
+                kind: ErrorKind::UnexpectedComma,
             });
-            Value::Int(0)
+            self.next_byte();
+            self.skip_ws_and_comments();
         }
     }
 
@@ -594,7 +1346,7 @@ impl<'a> Parser<'a> {
             if self.peek_byte() == Some(b'#') {
                 self.advance_byte(b'#');
                 while let Some(b) = self.next_byte() {
-                    if b == b'\n' {
+                    if b == b'\n' || b == b'\r' {
                         break;
                     }
                 }
@@ -625,7 +1377,7 @@ impl<'a> Parser<'a> {
             return;
         }
 
-        if self.peek_byte() == Some(b'\n') {
+        if self.at_line_end() {
             return;
         }
 
@@ -633,13 +1385,33 @@ impl<'a> Parser<'a> {
             return;
         }
 
+        // A closing brace may end a single-line struct, e.g. `pos { x 1 }`.
+        // At the top level the brace is reported as an unexpected character.
+        if self.peek_byte() == Some(b'}') {
+            return;
+        }
+
+        let Some(found) = self.peek_char() else {
+            return;
+        };
         self.errors.push(ParseError {
             line: self.line,
             column: self.column,
-            kind: ErrorKind::ExpectedNewlineAfterKeyValue,
+            kind: ErrorKind::ExpectedNewlineAfterKeyValue(found),
         });
     }
 
+    /// Whether the next byte ends the line, which may end in `\n`, `\r\n` or `\r`.
+    #[inline]
+    fn at_line_end(&self) -> bool {
+        matches!(self.peek_byte(), Some(b'\n' | b'\r'))
+    }
+
+    /// The character at the current position, which is always at a character boundary.
+    fn peek_char(&self) -> Option<char> {
+        self.slice_to_str(self.pos, self.len).chars().next()
+    }
+
     #[inline(always)]
     fn peek_byte(&self) -> Option<u8> {
         if self.pos < self.len {
@@ -665,7 +1437,8 @@ impl<'a> Parser<'a> {
     #[inline(always)]
     const fn advance_byte(&mut self, b: u8) {
         self.pos += 1;
-        if b == b'\n' {
+        // A lone `\r` ends a line too, but in `\r\n` only the `\n` counts
+        if b == b'\n' || (b == b'\r' && !(self.pos < self.len && self.input[self.pos] == b'\n')) {
             self.line += 1;
             self.column = 1;
         } else {
@@ -678,11 +1451,71 @@ impl<'a> Parser<'a> {
         self.pos >= self.len
     }
 
+    #[inline]
+    const fn position(&self) -> Position {
+        Position {
+            offset: self.pos,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    #[inline]
+    fn span_mark(&self) -> Option<SpanMark> {
+        self.spans.as_ref().map(SpanBuilder::mark)
+    }
+
+    /// Records the span of `value`, which started at `start`, if spans are collected.
+    #[inline]
+    fn close_span(&mut self, start: Position, mark: Option<SpanMark>, value: &Value) {
+        let end = self.position();
+        if let (Some(spans), Some(mark)) = (&mut self.spans, mark) {
+            spans.close(mark, Span { start, end }, value);
+        }
+    }
+
+    /// Records a scalar of `len` bytes from `start`, replacing any spans
+    /// recorded for the tokens it was assembled from.
+    fn replace_span_with_scalar(&mut self, start: Position, mark: Option<SpanMark>, len: usize) {
+        let (Some(spans), Some(mark)) = (&mut self.spans, mark) else {
+            return;
+        };
+        let mut end = start;
+        for &b in &self.input[start.offset..start.offset + len] {
+            end.offset += 1;
+            if b == b'\n' || (b == b'\r' && self.input.get(end.offset) != Some(&b'\n')) {
+                end.line += 1;
+                end.column = 1;
+            } else {
+                end.column += 1;
+            }
+        }
+        spans.replace_with_scalar(mark, Span { start, end });
+    }
+
+    /// Reports unquoted `text` starting at `at` unless it is a single bare word.
+    /// Only called in strict mode.
+    #[cold]
+    fn check_bare_text(&mut self, at: Position, text: &str) {
+        let kind = if text.contains(char::is_whitespace) {
+            ErrorKind::UnquotedString(text.to_owned())
+        } else if is_identifier(text) {
+            return;
+        } else {
+            ErrorKind::InvalidBareWord(text.to_owned())
+        };
+        self.errors.push(ParseError {
+            line: at.line,
+            column: at.column,
+            kind,
+        });
+    }
+
     /// Synchronize after an error
     /// Try to find a good place to resume, currently just advancing to the next newline or EOF.
     fn synchronize(&mut self) {
         while let Some(b) = self.peek_byte() {
-            if b == b'\n' {
+            if b == b'\n' || b == b'\r' {
                 self.next_byte();
                 break;
             }
@@ -692,7 +1525,227 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// The value of the unquoted words that are not strings.
+pub(crate) fn keyword(word: &str) -> Option<Value> {
+    match word {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        "null" => Some(Value::Null),
+        "inf" => Some(Value::Num(f64::INFINITY)),
+        "nan" => Some(Value::Num(f64::NAN)),
+        _ => None,
+    }
+}
+
+const fn is_malformed_number(kind: &ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::InvalidIntegerFormat(_)
+            | ErrorKind::InvalidFloatFormat(_)
+            | ErrorKind::InvalidDuration(_)
+            | ErrorKind::InvalidDateTime(_)
+    )
+}
+
+/// Whether a value that failed to parse as a number or date was still
+/// clearly written as one: with a radix prefix, as digits and `_` only (too
+/// large or misplaced separators), as a float too large for `f64`, with a
+/// duration unit, or starting with a `YYYY-MM-DD` date. Anything else, such as
+/// `1.2.3`, `1920x1080` or `1000-2000`, is read as text.
+fn meant_as_number(text: &str) -> bool {
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let date = text.as_bytes().get(..10).is_some_and(|date| {
+        date.iter().enumerate().all(|(index, b)| match index {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+    });
+    date || matches!(
+        unsigned.get(..2),
+        Some("0x" | "0X" | "0o" | "0O" | "0b" | "0B")
+    ) || unsigned.bytes().all(|b| b.is_ascii_digit() || b == b'_')
+        || (unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+            && unsigned
+                .replace('_', "")
+                .parse::<f64>()
+                .is_ok_and(f64::is_infinite))
+        || datetime::has_duration_unit(unsigned)
+}
+
+/// Reads a number: an integer in decimal, `0x` hex, `0o` octal or `0b`
+/// binary, or a decimal float with a fraction or an exponent. Any of them may
+/// have a sign and `_` between digits. Signed `inf` and `nan` are floats too.
+pub(crate) fn parse_number(text: &str, units: &UnitRegistry) -> Result<Value, ErrorKind> {
+    let (negative, unsigned) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let invalid_int = || ErrorKind::InvalidIntegerFormat(text.to_owned());
+    let invalid_float = || ErrorKind::InvalidFloatFormat(text.to_owned());
+    if let Some(Value::Num(n)) = keyword(unsigned) {
+        return Ok(Value::Num(if negative { -n } else { n }));
+    }
+
+    let radix = match unsigned.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+    // `0B` alone is zero bytes
+    if radix != 10 && unsigned.len() > 2 {
+        let digits = &unsigned[2..];
+        if !is_digit_group(digits, radix) {
+            return Err(invalid_int());
+        }
+        let magnitude =
+            u128::from_str_radix(&digits.replace('_', ""), radix).map_err(|_| invalid_int())?;
+        let value = if negative {
+            0_i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        };
+        return value.map(Value::integer).ok_or_else(invalid_int);
+    }
+
+    if datetime::has_duration_unit(unsigned) {
+        return datetime::parse_duration(unsigned)
+            .filter(|_| !negative)
+            .map(Value::Duration)
+            .ok_or_else(|| ErrorKind::InvalidDuration(text.to_owned()));
+    }
+    let unit_start = text
+        .rfind(|c: char| !(c.is_ascii_alphabetic() || c == '%'))
+        .map_or(0, |index| index + 1);
+    if unit_start > 0 && units.contains(&text[unit_start..]) {
+        let number = &text[..unit_start];
+        return parse_number(number, units)
+            .ok()
+            .and_then(|_| number.replace('_', "").parse().ok())
+            .filter(|amount: &f64| amount.is_finite())
+            .map(|amount| {
+                Value::Quantity(Quantity {
+                    amount,
+                    unit: text[unit_start..].to_owned(),
+                })
+            })
+            .ok_or_else(invalid_float);
+    }
+
+    let (mantissa, exponent) = unsigned.find(['e', 'E']).map_or((unsigned, None), |index| {
+        (&unsigned[..index], Some(&unsigned[index + 1..]))
+    });
+    let (whole, fraction) = mantissa
+        .split_once('.')
+        .map_or((mantissa, None), |(whole, fraction)| {
+            (whole, Some(fraction))
+        });
+    let is_float = fraction.is_some() || exponent.is_some();
+    let valid = if whole.is_empty() {
+        fraction.is_some_and(|fraction| is_digit_group(fraction, 10))
+    } else {
+        is_digit_group(whole, 10)
+            && fraction.is_none_or(|fraction| fraction.is_empty() || is_digit_group(fraction, 10))
+    } && exponent.is_none_or(|exponent| {
+        is_digit_group(exponent.strip_prefix(['+', '-']).unwrap_or(exponent), 10)
+    });
+    let digits = if text.contains('_') {
+        std::borrow::Cow::Owned(text.replace('_', ""))
+    } else {
+        std::borrow::Cow::Borrowed(text)
+    };
+    if is_float {
+        valid
+            .then(|| digits.parse().ok())
+            .flatten()
+            .filter(|number: &f64| number.is_finite())
+            .map(Value::Num)
+            .ok_or_else(invalid_float)
+    } else {
+        valid
+            .then(|| digits.parse().ok())
+            .flatten()
+            .map(Value::integer)
+            .ok_or_else(invalid_int)
+    }
+}
+
+/// The value of a base64 digit, in either the standard or the URL-safe alphabet.
+const fn base64_digit(b: u8) -> Option<u32> {
+    Some(match b {
+        b'A'..=b'Z' => (b - b'A') as u32,
+        b'a'..=b'z' => (b - b'a') as u32 + 26,
+        b'0'..=b'9' => (b - b'0') as u32 + 52,
+        b'+' | b'-' => 62,
+        b'/' | b'_' => 63,
+        _ => return None,
+    })
+}
+
+/// Whether `digits` are digits in `radix`, with `_` allowed between them.
+pub(crate) fn is_digit_group(digits: &str, radix: u32) -> bool {
+    !digits.is_empty()
+        && !digits.starts_with('_')
+        && !digits.ends_with('_')
+        && digits.chars().all(|c| c == '_' || c.is_digit(radix))
+}
+
+/// The length of the line break `text` starts with.
+fn line_break_len(text: &str) -> usize {
+    if text.starts_with("\r\n") { 2 } else { 1 }
+}
+
+/// `text` with every `\r\n` and lone `\r` turned into `\n`.
+fn normalize_line_breaks(text: &str) -> String {
+    if text.contains('\r') {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Whether `text` can be written as a bare word: a letter or `_`, followed by
+/// letters, digits and `_`, `-`, `.` or `/`.
+pub(crate) fn is_identifier(text: &str) -> bool {
+    let mut bytes = text.bytes();
+    let Some(first) = bytes.next() else {
+        return false;
+    };
+    (first.is_ascii_alphabetic() || first == b'_')
+        && bytes.all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.' | b'/'))
+}
+
+const fn ends_variant_name(b: u8) -> bool {
+    matches!(
+        b,
+        b' ' | b'\t' | b'\n' | b'\r' | b'{' | b'}' | b'[' | b']' | b')' | b'(' | b':' | b','
+    )
+}
+
+/// Whether `name` reads back whole as the name in `:name`.
+pub(crate) fn is_variant_name(name: &str) -> bool {
+    !name.is_empty() && !name.bytes().any(ends_variant_name)
+}
+
 impl Value {
+    #[must_use]
+    pub const fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Moves the value out, leaving [`Value::Null`] in its place.
+    #[must_use]
+    pub const fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::Null)
+    }
+
+    /// Puts `value` in place of this one and returns the old value.
+    #[must_use]
+    pub fn replace(&mut self, value: impl Into<Self>) -> Self {
+        std::mem::replace(self, value.into())
+    }
+
     #[must_use]
     pub const fn as_struct(&self) -> Option<&Struct> {
         if let Self::Struct(o) = self {
@@ -702,6 +1755,15 @@ impl Value {
         }
     }
 
+    #[must_use]
+    pub const fn as_struct_mut(&mut self) -> Option<&mut Struct> {
+        if let Self::Struct(o) = self {
+            Some(o)
+        } else {
+            None
+        }
+    }
+
     #[must_use]
     pub fn as_str(&self) -> Option<&str> {
         if let Self::Str(s) = self {
@@ -711,6 +1773,15 @@ impl Value {
         }
     }
 
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        if let Self::Bytes(b) = self {
+            Some(b)
+        } else {
+            None
+        }
+    }
+
     #[must_use]
     pub const fn as_num(&self) -> Option<f64> {
         if let Self::Num(n) = *self {
@@ -720,6 +1791,12 @@ impl Value {
         }
     }
 
+    /// `Value::Int` for integers that fit in an `i64`, else `Value::WideInt`.
+    #[must_use]
+    pub fn integer(value: i128) -> Self {
+        i64::try_from(value).map_or(Self::WideInt(value), Self::Int)
+    }
+
     #[must_use]
     pub const fn as_int(&self) -> Option<i64> {
         if let Self::Int(i) = *self {
@@ -729,6 +1806,29 @@ impl Value {
         }
     }
 
+    /// The integer as a `u64`, `None` if it is negative or too large.
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_integer()
+    }
+
+    /// Any integer, whether stored as [`Value::Int`] or [`Value::WideInt`].
+    #[must_use]
+    pub const fn as_i128(&self) -> Option<i128> {
+        match *self {
+            Self::Int(i) => Some(i as i128),
+            Self::WideInt(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// The integer converted to `T`, `None` if it is not an integer or does
+    /// not fit, as in `value.as_integer::<u16>()`.
+    #[must_use]
+    pub fn as_integer<T: TryFrom<i128>>(&self) -> Option<T> {
+        self.as_i128().and_then(|i| T::try_from(i).ok())
+    }
+
     #[must_use]
     pub const fn as_bool(&self) -> Option<bool> {
         if let Self::Bool(b) = *self {
@@ -738,6 +1838,33 @@ impl Value {
         }
     }
 
+    #[must_use]
+    pub const fn as_datetime(&self) -> Option<&DateTime> {
+        if let Self::DateTime(d) = self {
+            Some(d)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub const fn as_duration(&self) -> Option<std::time::Duration> {
+        if let Self::Duration(d) = *self {
+            Some(d)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub const fn as_quantity(&self) -> Option<&Quantity> {
+        if let Self::Quantity(q) = self {
+            Some(q)
+        } else {
+            None
+        }
+    }
+
     #[must_use]
     pub const fn as_array(&self) -> Option<&Vec<Self>> {
         if let Self::Array(a) = self {
@@ -747,6 +1874,15 @@ impl Value {
         }
     }
 
+    #[must_use]
+    pub const fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        if let Self::Array(a) = self {
+            Some(a)
+        } else {
+            None
+        }
+    }
+
     #[must_use]
     pub fn as_variant(&self) -> Option<&str> {
         if let Self::Variant(s, _) = self {
@@ -774,6 +1910,15 @@ impl Value {
         }
     }
 
+    #[must_use]
+    pub const fn as_tuple_mut(&mut self) -> Option<&mut Vec<Self>> {
+        if let Self::Tuple(items) = self {
+            Some(items)
+        } else {
+            None
+        }
+    }
+
     #[must_use]
     pub fn as_pair(&self) -> Option<(&Self, &Self)> {
         if let Self::Tuple(items) = self {
@@ -787,3 +1932,225 @@ impl Value {
         }
     }
 }
+
+// `SeqMap` only implements `PartialEq` for `Eq` values, which `f64` prevents,
+// so structs are compared entry by entry.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Null, Self::Null) => true,
+            (Self::Str(a), Self::Str(b)) => a == b,
+            (Self::Bytes(a), Self::Bytes(b)) => a == b,
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::WideInt(a), Self::WideInt(b)) => a == b,
+            (Self::Num(a), Self::Num(b)) => a == b,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::DateTime(a), Self::DateTime(b)) => a == b,
+            (Self::Duration(a), Self::Duration(b)) => a == b,
+            (Self::Quantity(a), Self::Quantity(b)) => a == b,
+            (Self::Variant(a, a_payload), Self::Variant(b, b_payload)) => {
+                a == b && a_payload == b_payload
+            }
+            (Self::Struct(a), Self::Struct(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a == b)
+            }
+            (Self::Array(a), Self::Array(b)) | (Self::Tuple(a), Self::Tuple(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+macro_rules! value_from {
+    ($($from:ty => |$value:ident| $to:expr),* $(,)?) => {$(
+        impl From<$from> for Value {
+            fn from($value: $from) -> Self {
+                $to
+            }
+        }
+    )*};
+}
+
+value_from! {
+    bool => |value| Self::Bool(value),
+    i8 => |value| Self::Int(value.into()),
+    i16 => |value| Self::Int(value.into()),
+    i32 => |value| Self::Int(value.into()),
+    i64 => |value| Self::Int(value),
+    u8 => |value| Self::Int(value.into()),
+    u16 => |value| Self::Int(value.into()),
+    u32 => |value| Self::Int(value.into()),
+    u64 => |value| Self::integer(value.into()),
+    i128 => |value| Self::integer(value),
+    usize => |value| Self::integer(i128::try_from(value).expect("usize fits in an i128")),
+    isize => |value| Self::integer(i128::try_from(value).expect("isize fits in an i128")),
+    f32 => |value| Self::Num(value.into()),
+    f64 => |value| Self::Num(value),
+    &str => |value| Self::Str(value.to_owned()),
+    String => |value| Self::Str(value),
+    char => |value| Self::Str(value.to_string()),
+    Struct => |value| Self::Struct(value),
+    DateTime => |value| Self::DateTime(value),
+    std::time::Duration => |value| Self::Duration(value),
+    Quantity => |value| Self::Quantity(value),
+}
+
+/// `None` is [`Value::Null`].
+impl<T: Into<Self>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+/// An array. Binary data is built with [`Value::Bytes`] instead.
+impl<T: Into<Self>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Self::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+macro_rules! value_from_tuple {
+    ($(($($name:ident),*))*) => {$(
+        #[allow(non_snake_case)]
+        impl<$($name: Into<Value>),*> From<($($name,)*)> for Value {
+            fn from(($($name,)*): ($($name,)*)) -> Self {
+                Self::Tuple(vec![$($name.into()),*])
+            }
+        }
+    )*};
+}
+
+value_from_tuple! {
+    (A, B)
+    (A, B, C)
+    (A, B, C, D)
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExpectedValueOnSameLine => {
+                f.write_str("expected a value on the same line as the key")
+            }
+            Self::ExpectedNewlineAfterKeyValue(found) => {
+                write!(f, "expected a line break after the value, found {found:?}")
+            }
+            Self::UnterminatedBlock => f.write_str("expected `}` before the end of input"),
+            Self::UnterminatedString => f.write_str("string is missing its closing `\"`"),
+            Self::InvalidUtf8InNumber => f.write_str("number is not valid UTF-8"),
+            Self::InvalidFloatFormat(text) => write!(f, "`{text}` is not a valid number"),
+            Self::InvalidIntegerFormat(text) => write!(f, "`{text}` is not a valid integer"),
+            Self::UnexpectedEndOfInput => f.write_str("unexpected end of input"),
+            Self::UnexpectedCharacter(found) => write!(f, "expected a key, found {found:?}"),
+            Self::DuplicateKey {
+                key,
+                first_line,
+                first_column,
+            } => write!(
+                f,
+                "duplicate key `{key}` (first defined at {first_line}:{first_column})"
+            ),
+            Self::UnquotedString(text) => write!(f, "`{text}` must be quoted to be one string"),
+            Self::InvalidBareWord(word) => write!(f, "`{word}` must be quoted"),
+            Self::UnexpectedComma => f.write_str("expected an item before `,`"),
+            Self::InvalidEscape(escape) => write!(f, "`{escape}` is not a valid escape"),
+            Self::InvalidDateTime(text) => write!(f, "`{text}` is not a valid date"),
+            Self::InvalidDuration(text) => write!(f, "`{text}` is not a valid duration"),
+            Self::InvalidByteDigit { found, encoding } => {
+                write!(f, "{found:?} is not a {encoding} digit")
+            }
+            Self::IncompleteBytes(encoding) => {
+                write!(f, "{encoding} data ends partway through a byte")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.kind, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Errors {
+    #[must_use]
+    pub fn as_slice(&self) -> &[ParseError] {
+        &self.0
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ParseError> {
+        self.0.iter()
+    }
+
+    #[must_use]
+    pub fn into_vec(self) -> Vec<ParseError> {
+        self.0
+    }
+}
+
+impl From<Vec<ParseError>> for Errors {
+    fn from(errors: Vec<ParseError>) -> Self {
+        Self(errors)
+    }
+}
+
+impl IntoIterator for Errors {
+    type Item = ParseError;
+    type IntoIter = std::vec::IntoIter<ParseError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Errors {
+    type Item = &'a ParseError;
+    type IntoIter = std::slice::Iter<'a, ParseError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.as_slice() {
+            [] => f.write_str("document had no errors"),
+            [error] => write!(f, "{error}"),
+            errors => {
+                write!(f, "document had {} errors", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// No `source`, as the errors are already part of the message
+impl std::error::Error for Errors {}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "could not read file: {error}"),
+            Self::Parse(errors) => write!(f, "{errors}"),
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            // The errors are already part of the message
+            Self::Parse(_) => None,
+        }
+    }
+}
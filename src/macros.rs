@@ -0,0 +1,125 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+/// Builds a [`Struct`](crate::Struct) in code, written much like a yini file.
+///
+/// Fields are `key: value` separated by commas, where a key is an identifier
+/// or a string literal. Values are written as:
+///
+/// - `{ key: value, ... }` for a struct, `[a, b]` for an array and `(a, b)`
+///   for a tuple
+/// - `:name`, `:name(a, b)`, `:name{ key: value }` or `:name[a, b]` for a
+///   variant
+/// - `null`
+/// - any other expression, converted with `Value::from`
+///
+/// ```
+/// use yini::{Value, yini};
+///
+/// let port = 8080;
+/// let config = yini! {
+///     name: "Main",
+///     server: {
+///         port: port,
+///         hosts: ["alpha", "beta"],
+///     },
+///     screen: :fullscreen(1024, 768),
+///     proxy: null,
+/// };
+/// assert_eq!(config.get("screen").and_then(Value::as_variant), Some("fullscreen"));
+/// ```
+///
+/// # Panics
+///
+/// Panics if a key is repeated.
+#[macro_export]
+macro_rules! yini {
+    // One field or item per step, so a literal nests one level deeper for
+    // each field or item rather than for each token
+    (@fields $map:ident) => {};
+    (@fields $map:ident $key:tt : : $name:ident $(, $($rest:tt)*)?) => {
+        $crate::yini!(@insert $map $key (: $name));
+        $crate::yini!(@fields $map $($($rest)*)?);
+    };
+    (@fields $map:ident $key:tt : : $name:ident $payload:tt $(, $($rest:tt)*)?) => {
+        $crate::yini!(@insert $map $key (: $name $payload));
+        $crate::yini!(@fields $map $($($rest)*)?);
+    };
+    (@fields $map:ident $key:tt : $value:tt $(, $($rest:tt)*)?) => {
+        $crate::yini!(@insert $map $key ($value));
+        $crate::yini!(@fields $map $($($rest)*)?);
+    };
+    (@fields $map:ident $key:tt : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::yini!(@insert $map $key ($value));
+        $crate::yini!(@fields $map $($($rest)*)?);
+    };
+    (@insert $map:ident $key:tt ($($value:tt)+)) => {
+        $map.insert($crate::yini!(@key $key), $crate::yini!(@value $($value)+))
+            .expect("key is repeated in `yini!`");
+    };
+    (@key $key:ident) => {
+        ::std::string::String::from(stringify!($key))
+    };
+    (@key $key:literal) => {
+        ::std::string::String::from($key)
+    };
+
+    // Items of arrays, tuples and payloads, separated by commas
+    (@items [$($done:expr),*]) => {
+        ::std::vec![$($done),*]
+    };
+    (@items [$($done:expr),*] : $name:ident $(, $($rest:tt)*)?) => {
+        $crate::yini!(@items [$($done,)* $crate::yini!(@value : $name)] $($($rest)*)?)
+    };
+    (@items [$($done:expr),*] : $name:ident $payload:tt $(, $($rest:tt)*)?) => {
+        $crate::yini!(@items [$($done,)* $crate::yini!(@value : $name $payload)] $($($rest)*)?)
+    };
+    (@items [$($done:expr),*] $item:tt $(, $($rest:tt)*)?) => {
+        $crate::yini!(@items [$($done,)* $crate::yini!(@value $item)] $($($rest)*)?)
+    };
+    (@items [$($done:expr),*] $item:expr $(, $($rest:tt)*)?) => {
+        $crate::yini!(@items [$($done,)* $crate::yini!(@value $item)] $($($rest)*)?)
+    };
+
+    (@value null) => {
+        $crate::Value::Null
+    };
+    (@value { $($fields:tt)* }) => {
+        $crate::Value::Struct($crate::yini!($($fields)*))
+    };
+    (@value [ $($items:tt)* ]) => {
+        $crate::Value::Array($crate::yini!(@items [] $($items)*))
+    };
+    (@value ( $($items:tt)* )) => {
+        $crate::Value::Tuple($crate::yini!(@items [] $($items)*))
+    };
+    (@value : $name:ident) => {
+        $crate::Value::Variant(::std::string::String::from(stringify!($name)), None)
+    };
+    (@value : $name:ident ( $($payload:tt)* )) => {
+        $crate::yini!(@variant $name ( $($payload)* ))
+    };
+    (@value : $name:ident { $($payload:tt)* }) => {
+        $crate::yini!(@variant $name { $($payload)* })
+    };
+    (@value : $name:ident [ $($payload:tt)* ]) => {
+        $crate::yini!(@variant $name [ $($payload)* ])
+    };
+    (@value $($value:tt)+) => {
+        $crate::Value::from($($value)+)
+    };
+    (@variant $name:ident $payload:tt) => {
+        $crate::Value::Variant(
+            ::std::string::String::from(stringify!($name)),
+            Some(::std::boxed::Box::new($crate::yini!(@value $payload))),
+        )
+    };
+
+    ($($fields:tt)*) => {{
+        let mut map = $crate::Struct::new();
+        $crate::yini!(@fields map $($fields)*);
+        map
+    }};
+}
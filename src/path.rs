@@ -0,0 +1,551 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Path expressions for reaching into nested values.
+//!
+//! [`PathQuery`] looks values up in a [`Struct`] or [`Value`] by a path such
+//! as `server.users[0].name`:
+//!
+//! | Segment     | Selects                                                   |
+//! |-------------|-----------------------------------------------------------|
+//! | `name`      | a struct field; digits also select an array or tuple item |
+//! | `"a key"`   | a struct field whose key needs quoting                    |
+//! | `[0]`       | an array or tuple item                                    |
+//! | `:name`     | the payload of the variant `name`; `:*` of any variant    |
+//! | `*`, `[*]`  | every field of a struct or item of an array or tuple      |
+//!
+//! Segments after the first start with `.`, `[` or `:`, so `mode:fullscreen.0`
+//! is the first payload item of `mode :fullscreen(1024 768)`. Inside quotes,
+//! `\` takes the next character literally. Wildcards match several values and
+//! are only accepted by [`PathQuery::query`].
+//!
+//! ```
+//! use yini::{Parser, PathQuery, Value};
+//!
+//! let root = Parser::new("server {\n    users [{ name alice }]\n}\n").parse();
+//! let name = root.get_path("server.users[0].name").unwrap();
+//! assert_eq!(name.as_str(), Some("alice"));
+//! ```
+
+use std::fmt;
+
+use crate::span::{Span, StructSpans};
+use crate::{Struct, Value};
+
+/// Looks up values by a path expression, see the [module docs](self).
+pub trait PathQuery {
+    /// The value at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `path` is malformed, contains a wildcard, or a segment does
+    /// not match the value it is applied to.
+    fn get_path(&self, path: &str) -> Result<&Value, PathError>;
+
+    /// The value at `path`, for changing it in place.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`get_path`](Self::get_path).
+    fn get_path_mut(&mut self, path: &str) -> Result<&mut Value, PathError>;
+
+    /// Every value `path` matches, in document order.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `path` is malformed or a segment does not match one of the
+    /// values it is applied to.
+    fn query(&self, path: &str) -> Result<Vec<&Value>, PathError>;
+}
+
+/// Why a path did not lead to a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    /// The path up to and including the segment that failed.
+    pub at: String,
+    /// Which segment failed, counting from zero.
+    pub segment: usize,
+    pub kind: PathErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// The path itself is malformed. Holds what is wrong with it.
+    Syntax(&'static str),
+    /// A struct without the field.
+    MissingKey(String),
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+    /// The segment does not apply to this kind of value.
+    WrongKind {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// `:name` reached a variant with another name.
+    VariantMismatch {
+        expected: String,
+        found: String,
+    },
+    /// `:name` reached a variant without a payload. Holds the variant name.
+    NoPayload(String),
+    /// A wildcard where only a single value can be returned.
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Variant(String),
+    AnyVariant,
+    Wildcard,
+}
+
+/// A parsed path, remembering where each segment ends for error messages.
+struct Path<'a> {
+    text: &'a str,
+    segments: Vec<(Segment, usize)>,
+}
+
+impl<'a> Path<'a> {
+    fn parse(text: &'a str) -> Result<Self, PathError> {
+        let mut reader = Reader {
+            text,
+            pos: 0,
+            segments: Vec::new(),
+        };
+        reader.path()?;
+        Ok(Self {
+            text,
+            segments: reader.segments,
+        })
+    }
+
+    fn error(&self, index: usize, kind: PathErrorKind) -> PathError {
+        PathError {
+            at: self.text[..self.segments[index].1].to_owned(),
+            segment: index,
+            kind,
+        }
+    }
+
+    /// Follows the segments from `start` on, beginning at `value`.
+    fn walk<'v>(&self, mut value: &'v Value, start: usize) -> Result<&'v Value, PathError> {
+        for (index, (segment, _)) in self.segments.iter().enumerate().skip(start) {
+            value = step(value, segment).map_err(|kind| self.error(index, kind))?;
+        }
+        Ok(value)
+    }
+
+    fn walk_mut<'v>(
+        &self,
+        mut value: &'v mut Value,
+        start: usize,
+    ) -> Result<&'v mut Value, PathError> {
+        for (index, (segment, _)) in self.segments.iter().enumerate().skip(start) {
+            value = step_mut(value, segment).map_err(|kind| self.error(index, kind))?;
+        }
+        Ok(value)
+    }
+
+    /// Adds every value matched from segment `index` on to `found`.
+    fn collect<'v>(
+        &self,
+        value: &'v Value,
+        index: usize,
+        found: &mut Vec<&'v Value>,
+    ) -> Result<(), PathError> {
+        let Some((segment, _)) = self.segments.get(index) else {
+            found.push(value);
+            return Ok(());
+        };
+        let matched = match (segment, value) {
+            (Segment::Wildcard, Value::Struct(fields)) => fields.values().collect(),
+            (Segment::Wildcard, Value::Array(items) | Value::Tuple(items)) => {
+                items.iter().collect()
+            }
+            (Segment::Wildcard, _) => {
+                return Err(self.error(index, wrong_kind(segment, value)));
+            }
+            _ => vec![step(value, segment).map_err(|kind| self.error(index, kind))?],
+        };
+        for value in matched {
+            self.collect(value, index + 1, found)?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits path text into segments.
+struct Reader<'a> {
+    text: &'a str,
+    pos: usize,
+    segments: Vec<(Segment, usize)>,
+}
+
+impl Reader<'_> {
+    fn path(&mut self) -> Result<(), PathError> {
+        let segment = match self.peek() {
+            Some(b'[') => self.bracket()?,
+            Some(b':') => self.variant()?,
+            _ => self.key()?,
+        };
+        self.segments.push((segment, self.pos));
+        while let Some(byte) = self.peek() {
+            let segment = match byte {
+                b'.' => {
+                    self.pos += 1;
+                    self.key()?
+                }
+                b'[' => self.bracket()?,
+                b':' => self.variant()?,
+                _ => return Err(self.error("expected `.`, `[` or `:`")),
+            };
+            self.segments.push((segment, self.pos));
+        }
+        Ok(())
+    }
+
+    /// A bare or quoted key, or `*`.
+    fn key(&mut self) -> Result<Segment, PathError> {
+        if self.peek() == Some(b'"') {
+            return self.quoted().map(Segment::Key);
+        }
+        match self.bare() {
+            "" => Err(self.error("expected a key")),
+            "*" => Ok(Segment::Wildcard),
+            key => Ok(Segment::Key(key.to_owned())),
+        }
+    }
+
+    /// `[0]`, `[*]` or `["key"]`.
+    fn bracket(&mut self) -> Result<Segment, PathError> {
+        self.pos += 1;
+        let segment = match self.peek() {
+            Some(b'"') => Segment::Key(self.quoted()?),
+            Some(b'*') => {
+                self.pos += 1;
+                Segment::Wildcard
+            }
+            Some(b'0'..=b'9') => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(b'0'..=b'9')) {
+                    self.pos += 1;
+                }
+                let index = self.text[start..self.pos]
+                    .parse()
+                    .map_err(|_| self.error("expected an index that fits in a `usize`"))?;
+                Segment::Index(index)
+            }
+            _ => return Err(self.error("expected an index, `*` or a quoted key")),
+        };
+        if self.peek() != Some(b']') {
+            return Err(self.error("expected `]`"));
+        }
+        self.pos += 1;
+        Ok(segment)
+    }
+
+    /// `:name` or `:*`.
+    fn variant(&mut self) -> Result<Segment, PathError> {
+        self.pos += 1;
+        match self.bare() {
+            "" => Err(self.error("expected a variant name")),
+            "*" => Ok(Segment::AnyVariant),
+            name => Ok(Segment::Variant(name.to_owned())),
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, PathError> {
+        self.pos += 1;
+        let mut key = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok(key);
+                }
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        key.push(escaped);
+                    }
+                }
+                _ => key.push(c),
+            }
+        }
+        self.pos = self.text.len();
+        Err(self.error("quoted key is missing its closing `\"`"))
+    }
+
+    /// Text up to the next separator or whitespace.
+    fn bare(&mut self) -> &str {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if matches!(byte, b'.' | b'[' | b']' | b':' | b'"') || byte.is_ascii_whitespace() {
+                break;
+            }
+            self.pos += 1;
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    /// A syntax error at the current character.
+    fn error(&self, expected: &'static str) -> PathError {
+        let end = self.text[self.pos..]
+            .chars()
+            .next()
+            .map_or(self.text.len(), |c| self.pos + c.len_utf8());
+        PathError {
+            at: self.text[..end].to_owned(),
+            segment: self.segments.len(),
+            kind: PathErrorKind::Syntax(expected),
+        }
+    }
+}
+
+fn step<'v>(value: &'v Value, segment: &Segment) -> Result<&'v Value, PathErrorKind> {
+    match (segment, value) {
+        (_, Value::Struct(fields)) => field(fields, segment),
+        (Segment::Index(index), Value::Array(items) | Value::Tuple(items)) => {
+            items.get(*index).ok_or(PathErrorKind::IndexOutOfRange {
+                index: *index,
+                len: items.len(),
+            })
+        }
+        (Segment::Key(key), Value::Array(items) | Value::Tuple(items)) => {
+            let index = item_index(key).ok_or_else(|| wrong_kind(segment, value))?;
+            items.get(index).ok_or(PathErrorKind::IndexOutOfRange {
+                index,
+                len: items.len(),
+            })
+        }
+        (Segment::Variant(_) | Segment::AnyVariant, Value::Variant(name, payload)) => {
+            check_variant(segment, name)?;
+            payload
+                .as_deref()
+                .ok_or_else(|| PathErrorKind::NoPayload(name.clone()))
+        }
+        (Segment::Wildcard, _) => Err(PathErrorKind::Wildcard),
+        _ => Err(wrong_kind(segment, value)),
+    }
+}
+
+fn step_mut<'v>(value: &'v mut Value, segment: &Segment) -> Result<&'v mut Value, PathErrorKind> {
+    let found = kind_name(value);
+    let wrong_kind = || PathErrorKind::WrongKind {
+        expected: segment.expects(),
+        found,
+    };
+    match (segment, value) {
+        (_, Value::Struct(fields)) => field_mut(fields, segment),
+        (Segment::Index(index), Value::Array(items) | Value::Tuple(items)) => {
+            let len = items.len();
+            items
+                .get_mut(*index)
+                .ok_or(PathErrorKind::IndexOutOfRange { index: *index, len })
+        }
+        (Segment::Key(key), Value::Array(items) | Value::Tuple(items)) => {
+            let index = item_index(key).ok_or_else(wrong_kind)?;
+            let len = items.len();
+            items
+                .get_mut(index)
+                .ok_or(PathErrorKind::IndexOutOfRange { index, len })
+        }
+        (Segment::Variant(_) | Segment::AnyVariant, Value::Variant(name, payload)) => {
+            check_variant(segment, name)?;
+            payload
+                .as_deref_mut()
+                .ok_or_else(|| PathErrorKind::NoPayload(name.clone()))
+        }
+        (Segment::Wildcard, _) => Err(PathErrorKind::Wildcard),
+        _ => Err(wrong_kind()),
+    }
+}
+
+fn field<'v>(fields: &'v Struct, segment: &Segment) -> Result<&'v Value, PathErrorKind> {
+    match segment {
+        Segment::Key(key) => fields
+            .get(key.as_str())
+            .ok_or_else(|| PathErrorKind::MissingKey(key.clone())),
+        Segment::Wildcard => Err(PathErrorKind::Wildcard),
+        _ => Err(PathErrorKind::WrongKind {
+            expected: segment.expects(),
+            found: "a struct",
+        }),
+    }
+}
+
+fn field_mut<'v>(
+    fields: &'v mut Struct,
+    segment: &Segment,
+) -> Result<&'v mut Value, PathErrorKind> {
+    match segment {
+        Segment::Key(key) => fields
+            .get_mut(key)
+            .ok_or_else(|| PathErrorKind::MissingKey(key.clone())),
+        Segment::Wildcard => Err(PathErrorKind::Wildcard),
+        _ => Err(PathErrorKind::WrongKind {
+            expected: segment.expects(),
+            found: "a struct",
+        }),
+    }
+}
+
+/// A key of only digits, such as the `0` in `users.0`, as an item index.
+fn item_index(key: &str) -> Option<usize> {
+    if key.bytes().all(|b| b.is_ascii_digit()) {
+        key.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn check_variant(segment: &Segment, found: &str) -> Result<(), PathErrorKind> {
+    match segment {
+        Segment::Variant(expected) if expected != found => Err(PathErrorKind::VariantMismatch {
+            expected: expected.clone(),
+            found: found.to_owned(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+const fn wrong_kind(segment: &Segment, value: &Value) -> PathErrorKind {
+    PathErrorKind::WrongKind {
+        expected: segment.expects(),
+        found: kind_name(value),
+    }
+}
+
+impl Segment {
+    const fn expects(&self) -> &'static str {
+        match self {
+            Self::Key(_) => "a struct",
+            Self::Index(_) => "an array or tuple",
+            Self::Variant(_) | Self::AnyVariant => "a variant",
+            Self::Wildcard => "a struct, array or tuple",
+        }
+    }
+}
+
+/// How a value is named in error messages.
+pub(crate) const fn kind_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Str(_) => "a string",
+        Value::Bytes(_) => "bytes",
+        Value::Int(_) | Value::WideInt(_) => "an integer",
+        Value::Num(_) => "a float",
+        Value::Bool(_) => "a boolean",
+        Value::DateTime(_) => "a date",
+        Value::Duration(_) => "a duration",
+        Value::Quantity(_) => "a quantity",
+        Value::Variant(..) => "a variant",
+        Value::Struct(_) => "a struct",
+        Value::Array(_) => "an array",
+        Value::Tuple(_) => "a tuple",
+    }
+}
+
+/// The span of the value that the first `depth` segments of `path` lead to,
+/// `None` for the root or a path that does not lead anywhere in `spans`.
+pub(crate) fn locate(spans: &StructSpans, path: &str, depth: usize) -> Option<Span> {
+    let path = Path::parse(path).ok()?;
+    let mut segments = path.segments.iter().take(depth).map(|(segment, _)| segment);
+    let Segment::Key(key) = segments.next()? else {
+        return None;
+    };
+    let mut span = &spans.get(key.as_str())?.value;
+    for segment in segments {
+        span = match segment {
+            Segment::Key(key) => match span.field(key) {
+                Some(field) => &field.value,
+                None => span.item(item_index(key)?)?,
+            },
+            Segment::Index(index) => span.item(*index)?,
+            Segment::Variant(_) | Segment::AnyVariant => span.payload()?,
+            Segment::Wildcard => return None,
+        };
+    }
+    Some(span.span)
+}
+
+impl PathQuery for Value {
+    fn get_path(&self, path: &str) -> Result<&Value, PathError> {
+        Path::parse(path)?.walk(self, 0)
+    }
+
+    fn get_path_mut(&mut self, path: &str) -> Result<&mut Value, PathError> {
+        Path::parse(path)?.walk_mut(self, 0)
+    }
+
+    fn query(&self, path: &str) -> Result<Vec<&Value>, PathError> {
+        let path = Path::parse(path)?;
+        let mut found = Vec::new();
+        path.collect(self, 0, &mut found)?;
+        Ok(found)
+    }
+}
+
+impl PathQuery for Struct {
+    fn get_path(&self, path: &str) -> Result<&Value, PathError> {
+        let path = Path::parse(path)?;
+        let value = field(self, &path.segments[0].0).map_err(|kind| path.error(0, kind))?;
+        path.walk(value, 1)
+    }
+
+    fn get_path_mut(&mut self, path: &str) -> Result<&mut Value, PathError> {
+        let path = Path::parse(path)?;
+        let value = field_mut(self, &path.segments[0].0).map_err(|kind| path.error(0, kind))?;
+        path.walk_mut(value, 1)
+    }
+
+    fn query(&self, path: &str) -> Result<Vec<&Value>, PathError> {
+        let path = Path::parse(path)?;
+        let mut found = Vec::new();
+        if path.segments[0].0 == Segment::Wildcard {
+            for value in self.values() {
+                path.collect(value, 1, &mut found)?;
+            }
+        } else {
+            let value = field(self, &path.segments[0].0).map_err(|kind| path.error(0, kind))?;
+            path.collect(value, 1, &mut found)?;
+        }
+        Ok(found)
+    }
+}
+
+impl fmt::Display for PathErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(expected) => f.write_str(expected),
+            Self::MissingKey(key) => write!(f, "no field `{key}`"),
+            Self::IndexOutOfRange { index, len } => {
+                write!(f, "index {index} is out of range for {len} items")
+            }
+            Self::WrongKind { expected, found } => write!(f, "expected {expected}, found {found}"),
+            Self::VariantMismatch { expected, found } => {
+                write!(f, "expected variant `:{expected}`, found `:{found}`")
+            }
+            Self::NoPayload(name) => write!(f, "variant `:{name}` has no payload"),
+            Self::Wildcard => f.write_str("a wildcard matches several values, use `query`"),
+        }
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at `{}`", self.kind, self.at)
+    }
+}
+
+impl std::error::Error for PathError {}
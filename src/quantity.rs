@@ -0,0 +1,203 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Numbers with a unit, such as sizes, percentages and angles.
+//!
+//! A number directly followed by a known unit reads as a
+//! [`Value::Quantity`](crate::Value::Quantity):
+//!
+//! ```text
+//! cache  64MB
+//! volume 80%
+//! fov    90deg
+//! ```
+//!
+//! Which units are known is decided by the [`UnitRegistry`] in
+//! [`ParserOptions`](crate::ParserOptions). Each unit is a multiple of a base
+//! unit, so quantities can be converted between units of the same kind.
+//! Duration units such as `ms` and `h` always read as
+//! [`Value::Duration`](crate::Value::Duration) instead, and a number followed
+//! by an unknown unit, such as `5kg`, reads as a string.
+
+use std::fmt;
+
+/// A number and its unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub amount: f64,
+    pub unit: String,
+}
+
+/// A unit and how it relates to its base unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    pub name: String,
+    pub base: String,
+    /// How many base units one of this unit is.
+    pub factor: f64,
+}
+
+/// Why [`UnitRegistry::insert`] refused a unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitError {
+    /// The name is empty or holds something other than ASCII letters and `%`.
+    InvalidName(String),
+    /// The name ends in a duration unit such as `s` or `ms`, so numbers
+    /// followed by it read as durations.
+    DurationUnit(String),
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(name) => {
+                write!(f, "`{name}` is not a valid unit name, use letters and `%`")
+            }
+            Self::DurationUnit(name) => {
+                write!(f, "`{name}` would be read as a duration unit")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+/// The units a parser accepts after numbers.
+#[derive(Debug, Clone)]
+pub struct UnitRegistry {
+    custom: Vec<Unit>,
+    standard: bool,
+}
+
+/// `(name, base, factor)` of the units known by default.
+const STANDARD_UNITS: [(&str, &str, f64); 18] = [
+    ("B", "B", 1.0),
+    ("kB", "B", 1e3),
+    ("KB", "B", 1e3),
+    ("MB", "B", 1e6),
+    ("GB", "B", 1e9),
+    ("TB", "B", 1e12),
+    ("KiB", "B", 1024.0),
+    ("MiB", "B", 1_048_576.0),
+    ("GiB", "B", 1_073_741_824.0),
+    ("TiB", "B", 1_099_511_627_776.0),
+    ("%", "%", 1.0),
+    ("rad", "rad", 1.0),
+    ("deg", "rad", std::f64::consts::PI / 180.0),
+    ("turn", "rad", std::f64::consts::TAU),
+    ("Hz", "Hz", 1.0),
+    ("kHz", "Hz", 1e3),
+    ("MHz", "Hz", 1e6),
+    ("GHz", "Hz", 1e9),
+];
+
+impl UnitRegistry {
+    /// The standard units: bytes (`B`, `kB`/`KB`, `MB`, `GB`, `TB`, `KiB`,
+    /// `MiB`, `GiB`, `TiB`), `%`, angles (`rad`, `deg`, `turn`) and
+    /// frequencies (`Hz`, `kHz`, `MHz`, `GHz`).
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            custom: Vec::new(),
+            standard: true,
+        }
+    }
+
+    /// A registry without any units, so that numbers never take a suffix.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self {
+            custom: Vec::new(),
+            standard: false,
+        }
+    }
+
+    /// Adds `name` as `factor` times `base`, replacing any unit with the same
+    /// name. A base unit is added with itself as `base` and a factor of 1.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a number followed by `name` would not read as a quantity.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        base: impl Into<String>,
+        factor: f64,
+    ) -> Result<(), UnitError> {
+        let name = name.into();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic() || c == '%') {
+            return Err(UnitError::InvalidName(name));
+        }
+        if crate::datetime::has_duration_unit(&name) {
+            return Err(UnitError::DurationUnit(name));
+        }
+        self.custom.retain(|unit| unit.name != name);
+        self.custom.push(Unit {
+            name,
+            base: base.into(),
+            factor,
+        });
+        Ok(())
+    }
+
+    /// The base unit of `name` and how many of them one `name` is.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<(&str, f64)> {
+        if let Some(unit) = self.custom.iter().find(|unit| unit.name == name) {
+            return Some((&unit.base, unit.factor));
+        }
+        if !self.standard {
+            return None;
+        }
+        STANDARD_UNITS
+            .iter()
+            .find(|(unit, _, _)| *unit == name)
+            .map(|(_, base, factor)| (*base, *factor))
+    }
+
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Quantity {
+    /// The quantity in its base unit, such as `64MB` as `64000000B`. `None`
+    /// if `units` does not know the unit.
+    #[must_use]
+    pub fn to_base(&self, units: &UnitRegistry) -> Option<Self> {
+        let (base, factor) = units.get(&self.unit)?;
+        Some(Self {
+            amount: self.amount * factor,
+            unit: base.to_string(),
+        })
+    }
+
+    /// The quantity in `unit`, `None` unless both units are known and share
+    /// a base unit.
+    #[must_use]
+    pub fn convert(&self, unit: &str, units: &UnitRegistry) -> Option<Self> {
+        let base = self.to_base(units)?;
+        let (target_base, factor) = units.get(unit)?;
+        (base.unit == target_base).then(|| Self {
+            amount: base.amount / factor,
+            unit: unit.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The unit already tells it apart from an integer, so whole amounts
+        // need no fraction
+        write!(f, "{}{}", self.amount, self.unit)
+    }
+}
@@ -0,0 +1,845 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Serde serialization into a [`Value`] tree or yini text.
+//!
+//! Enums are written as variants the same way [`crate::de`] reads them: unit
+//! variants as `:name`, newtype variants as `:name(x)`, tuple variants as
+//! `:name(a b)` and struct variants as `:name{...}`. Fields holding `None` are
+//! left out, and `None` anywhere else is written as `null`, so a field holding
+//! `Some(None)` is written as an explicit `null`. Nested options collapse:
+//! that `null` reads back as `None`, as yini has only one kind of null.
+//!
+//! A `std::time::Duration` is written as a struct of `secs` and `nanos`, as
+//! serde gives no way to tell it apart from any other struct. Mark a field
+//! with `#[serde(with = "yini::ser::duration")]` to write it as a duration
+//! literal such as `1500ms` instead. Either form reads back into a `Duration`.
+
+use std::fmt;
+
+use seq_map::SeqMap;
+use serde::ser::{self, Serialize};
+
+use crate::writer::{WriteOptions, write_struct_with};
+use crate::{Struct, Value};
+
+/// A serialization error.
+#[derive(Debug, Clone)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Serializes `value` as a yini document.
+///
+/// # Errors
+///
+/// Returns an error if `value` is not a struct or map, or contains something
+/// yini can not represent.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    to_string_with(value, &WriteOptions::default())
+}
+
+/// Serializes `value` as a yini document using `options`.
+///
+/// # Errors
+///
+/// Returns an error if `value` is not a struct or map, or contains something
+/// yini can not represent.
+pub fn to_string_with<T: Serialize + ?Sized>(
+    value: &T,
+    options: &WriteOptions,
+) -> Result<String, Error> {
+    match to_value(value)? {
+        Value::Struct(root) => write_struct_with(&root, options).map_err(ser::Error::custom),
+        _ => Err(ser::Error::custom(
+            "a yini document must be a struct or a map",
+        )),
+    }
+}
+
+/// Serializes `value` into a [`Value`] tree.
+///
+/// # Errors
+///
+/// Returns an error if `value` contains something yini can not represent.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value.serialize(Serializer)
+}
+
+/// Serializes into a [`Value`].
+pub struct Serializer;
+
+fn int<T: TryInto<i128>>(v: T) -> Result<Value, Error> {
+    v.try_into()
+        .map(Value::integer)
+        .map_err(|_| ser::Error::custom("integer does not fit in a signed 128-bit number"))
+}
+
+fn variant(name: &str, payload: Value) -> Value {
+    Value::Variant(name.to_string(), Some(Box::new(payload)))
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SerializeItems;
+    type SerializeTuple = SerializeItems;
+    type SerializeTupleStruct = SerializeItems;
+    type SerializeTupleVariant = SerializeItems;
+    type SerializeMap = SerializeFields;
+    type SerializeStruct = SerializeFields;
+    type SerializeStructVariant = SerializeFields;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, Error> {
+        int(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::Num(f64::from(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::Num(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::Str(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::Str(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Tuple(Vec::new()))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::Variant(variant.to_string(), None))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let value = value.serialize(self)?;
+        if name == duration::MARKER
+            && let Value::Struct(fields) = &value
+            && let Some(duration) = std_duration(fields)
+        {
+            return Ok(Value::Duration(duration));
+        }
+        Ok(value)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        Ok(variant(name, Value::Tuple(vec![value.serialize(self)?])))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeItems, Error> {
+        Ok(SerializeItems::new(len.unwrap_or(0), Shape::Array))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeItems, Error> {
+        Ok(SerializeItems::new(len, Shape::Tuple))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeItems, Error> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeItems, Error> {
+        Ok(SerializeItems::new(len, Shape::Variant(variant)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeFields, Error> {
+        Ok(SerializeFields::new(None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<SerializeFields, Error> {
+        Ok(SerializeFields::new(None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeFields, Error> {
+        Ok(SerializeFields::new(Some(variant)))
+    }
+}
+
+enum Shape {
+    Array,
+    Tuple,
+    Variant(&'static str),
+}
+
+#[doc(hidden)]
+pub struct SerializeItems {
+    items: Vec<Value>,
+    shape: Shape,
+}
+
+impl SerializeItems {
+    fn new(len: usize, shape: Shape) -> Self {
+        Self {
+            items: Vec::with_capacity(len),
+            shape,
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        match self.shape {
+            Shape::Array => Value::Array(self.items),
+            Shape::Tuple => Value::Tuple(self.items),
+            Shape::Variant(name) => variant(name, Value::Tuple(self.items)),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeItems {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for SerializeItems {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeItems {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeItems {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+#[doc(hidden)]
+pub struct SerializeFields {
+    fields: Struct,
+    next_key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeFields {
+    fn new(variant: Option<&'static str>) -> Self {
+        Self {
+            fields: SeqMap::new(),
+            next_key: None,
+            variant,
+        }
+    }
+
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), Error> {
+        // Fields that are `None` are left out rather than written as `null`,
+        // while a `Some(None)` or a `Value::Null` inside is still written
+        if matches!(value.serialize(IsNone), Ok(true)) {
+            return Ok(());
+        }
+        let value = value.serialize(Serializer)?;
+        self.fields
+            .insert(key, value)
+            .map_err(|_| ser::Error::custom("duplicate key"))
+    }
+
+    fn finish(self) -> Value {
+        match self.variant {
+            Some(name) => variant(name, Value::Struct(self.fields)),
+            None => Value::Struct(self.fields),
+        }
+    }
+}
+
+/// Writes a `std::time::Duration` field as a duration literal, for use with
+/// `#[serde(with = "yini::ser::duration")]`.
+///
+/// Other formats see the duration as if the attribute was not there.
+pub mod duration {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// The newtype name that [`super::Serializer`] turns into a duration.
+    pub(super) const MARKER: &str = "$yini::Duration";
+
+    /// Serializes `duration` so that yini writes it as a duration literal.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `serializer`.
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(MARKER, duration)
+    }
+
+    /// Deserializes a duration the same way as without the attribute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value is not a duration.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Duration::deserialize(deserializer)
+    }
+}
+
+/// The `std::time::Duration` serialized as `fields`.
+fn std_duration(fields: &Struct) -> Option<std::time::Duration> {
+    let keys: Vec<&str> = fields.keys().map(String::as_str).collect();
+    if keys != ["secs", "nanos"] {
+        return None;
+    }
+    let secs = fields.get("secs")?.as_u64()?;
+    let nanos = fields.get("nanos")?.as_integer::<u32>()?;
+    (nanos < 1_000_000_000).then(|| std::time::Duration::new(secs, nanos))
+}
+
+impl ser::SerializeMap for SerializeFields {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.next_key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| ser::Error::custom("value serialized before key"))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStruct for SerializeFields {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeFields {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+/// Turns map keys into strings. Only strings, characters, integers, booleans
+/// and unit variants make sense as keys.
+struct KeySerializer;
+
+fn key_error() -> Error {
+    ser::Error::custom("map keys must be strings, integers, booleans or unit variants")
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<String, Error>;
+    type SerializeTuple = ser::Impossible<String, Error>;
+    type SerializeTupleStruct = ser::Impossible<String, Error>;
+    type SerializeTupleVariant = ser::Impossible<String, Error>;
+    type SerializeMap = ser::Impossible<String, Error>;
+    type SerializeStruct = ser::Impossible<String, Error>;
+    type SerializeStructVariant = ser::Impossible<String, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, Error> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(key_error())
+    }
+}
+
+/// Tells whether a value serializes as `None`, without serializing any more
+/// of it. Containers answer with an error, as they are never `None`.
+struct IsNone;
+
+fn not_none() -> Error {
+    ser::Error::custom("value is not `None`")
+}
+
+impl ser::Serializer for IsNone {
+    type Ok = bool;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<bool, Error>;
+    type SerializeTuple = ser::Impossible<bool, Error>;
+    type SerializeTupleStruct = ser::Impossible<bool, Error>;
+    type SerializeTupleVariant = ser::Impossible<bool, Error>;
+    type SerializeMap = ser::Impossible<bool, Error>;
+    type SerializeStruct = ser::Impossible<bool, Error>;
+    type SerializeStructVariant = ser::Impossible<bool, Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_i128(self, _v: i128) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_u128(self, _v: u128) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_char(self, _v: char) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_none(self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_unit(self) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<bool, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(not_none())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(not_none())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(not_none())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(not_none())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(not_none())
+    }
+}
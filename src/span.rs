@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Source locations for parsed keys and values.
+//!
+//! [`Parser::parse_spanned`](crate::Parser::parse_spanned) returns a span tree
+//! next to the usual [`Struct`]. It has the same shape as the value tree, so a
+//! value reached through `root.get("server")` has its location at
+//! `spans.get("server")`. When a key is repeated, its spans are those of the
+//! first occurrence, whichever value the duplicate key policy keeps.
+
+use seq_map::SeqMap;
+
+use crate::{Struct, Value};
+
+/// A point in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    /// Byte offset from the start of the input.
+    pub offset: usize,
+    /// One-based line.
+    pub line: usize,
+    /// One-based column, counted in bytes.
+    pub column: usize,
+}
+
+/// The range `start..end` of some source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// The byte range, for slicing the source text.
+    #[must_use]
+    pub const fn range(&self) -> std::ops::Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}
+
+/// The location of a value and of everything inside it.
+#[derive(Debug, Clone)]
+pub struct ValueSpan {
+    pub span: Span,
+    pub children: SpanChildren,
+}
+
+/// Locations of the parts of a value, mirroring [`Value`].
+#[derive(Debug, Clone)]
+pub enum SpanChildren {
+    /// Strings, numbers and booleans.
+    None,
+    /// The fields of a [`Value::Struct`].
+    Fields(StructSpans),
+    /// The items of a [`Value::Array`] or [`Value::Tuple`].
+    Items(Vec<ValueSpan>),
+    /// The name and optional payload of a [`Value::Variant`].
+    Variant {
+        name: Span,
+        payload: Option<Box<ValueSpan>>,
+    },
+}
+
+/// The location of a `key value` line.
+#[derive(Debug, Clone)]
+pub struct FieldSpan {
+    pub key: Span,
+    pub value: ValueSpan,
+}
+
+/// Field locations of a struct, in the same order as its [`Struct`].
+pub type StructSpans = SeqMap<String, FieldSpan>;
+
+impl ValueSpan {
+    /// The location of field `key`, if this is a struct.
+    #[must_use]
+    pub fn field(&self, key: &str) -> Option<&FieldSpan> {
+        match &self.children {
+            SpanChildren::Fields(fields) => fields.get(key),
+            _ => None,
+        }
+    }
+
+    /// The location of item `index`, if this is an array or tuple.
+    #[must_use]
+    pub fn item(&self, index: usize) -> Option<&Self> {
+        match &self.children {
+            SpanChildren::Items(items) => items.get(index),
+            _ => None,
+        }
+    }
+
+    /// The location of the variant payload, if this is a variant with one.
+    #[must_use]
+    pub fn payload(&self) -> Option<&Self> {
+        match &self.children {
+            SpanChildren::Variant { payload, .. } => payload.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Which span stacks a value's children start at.
+#[derive(Clone, Copy)]
+pub(crate) struct SpanMark {
+    values: usize,
+    keys: usize,
+}
+
+/// Collects spans while parsing. Finished values and keys are pushed in source
+/// order and gathered into their parent when the parent closes.
+#[derive(Default)]
+pub(crate) struct SpanBuilder {
+    values: Vec<ValueSpan>,
+    keys: Vec<Span>,
+}
+
+impl SpanBuilder {
+    pub(crate) const fn mark(&self) -> SpanMark {
+        SpanMark {
+            values: self.values.len(),
+            keys: self.keys.len(),
+        }
+    }
+
+    pub(crate) fn push_key(&mut self, key: Span) {
+        self.keys.push(key);
+    }
+
+    /// Forgets the most recent key, and its value when `with_value` is set.
+    pub(crate) fn discard_field(&mut self, with_value: bool) {
+        self.keys.pop();
+        if with_value {
+            self.values.pop();
+        }
+    }
+
+    /// Replaces everything since `mark` with a single scalar span.
+    pub(crate) fn replace_with_scalar(&mut self, mark: SpanMark, span: Span) {
+        self.values.truncate(mark.values);
+        self.keys.truncate(mark.keys);
+        self.values.push(ValueSpan {
+            span,
+            children: SpanChildren::None,
+        });
+    }
+
+    /// Finishes `value`, taking the spans of its children pushed since `mark`.
+    pub(crate) fn close(&mut self, mark: SpanMark, span: Span, value: &Value) {
+        let children = match value {
+            Value::Struct(fields) => SpanChildren::Fields(self.take_fields(mark, fields)),
+            Value::Array(_) | Value::Tuple(_) => {
+                SpanChildren::Items(self.values.drain(mark.values..).collect())
+            }
+            Value::Variant(name, _) => {
+                let start = Position {
+                    offset: span.start.offset + 1,
+                    line: span.start.line,
+                    column: span.start.column + 1,
+                };
+                let end = Position {
+                    offset: start.offset + name.len(),
+                    line: start.line,
+                    column: start.column + name.len(),
+                };
+                SpanChildren::Variant {
+                    name: Span { start, end },
+                    payload: self.values.drain(mark.values..).next().map(Box::new),
+                }
+            }
+            _ => SpanChildren::None,
+        };
+        self.values.push(ValueSpan { span, children });
+    }
+
+    /// Finishes the top-level struct.
+    pub(crate) fn finish(mut self, root: &Struct) -> StructSpans {
+        self.take_fields(SpanMark { values: 0, keys: 0 }, root)
+    }
+
+    fn take_fields(&mut self, mark: SpanMark, fields: &Struct) -> StructSpans {
+        let mut spans = SeqMap::new();
+        let keys = self.keys.drain(mark.keys..);
+        let values = self.values.drain(mark.values..);
+        for ((name, key), value) in fields.keys().zip(keys).zip(values) {
+            let _ = spans.insert(name.clone(), FieldSpan { key, value });
+        }
+        spans
+    }
+}
@@ -0,0 +1,536 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+//! Turns a [`Struct`] or [`Value`] tree back into yini text.
+//!
+//! The output is always accepted by [`Parser::parse`](crate::Parser::parse) and
+//! reads back to an equal tree. Layout is controlled by [`WriteOptions`].
+//!
+//! A [`Value`] does not record how it was written, so integers come out in
+//! decimal even if they were read as `0xFF`. To keep the original base,
+//! comments and layout, edit the text through
+//! [`Document`](crate::document::Document) instead.
+
+use std::fmt::{self, Write as _};
+
+use crate::quantity::UnitRegistry;
+use crate::{Struct, Value};
+
+/// How nested lines are indented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(usize),
+    Tab,
+}
+
+/// What goes between the items of arrays and tuples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `[1 2 3]`
+    Whitespace,
+    /// `[1, 2, 3]`
+    Comma,
+}
+
+/// How lines end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /// The style of the first line break in `text`, `Lf` if there is none.
+    #[must_use]
+    pub fn detect(text: &str) -> Self {
+        match text.find(['\n', '\r']) {
+            Some(index) if text[index..].starts_with("\r\n") => Self::CrLf,
+            _ => Self::Lf,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Formatting choices for the writer.
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    pub indent: Indent,
+    /// Write the optional colon directly after keys (`key: value`).
+    pub key_colon: bool,
+    pub separator: Separator,
+    /// Arrays of scalars that would make a line longer than this are wrapped
+    /// onto several lines, as many items per line as fit.
+    pub max_width: usize,
+    /// Write structs with a single simple field on one line (`{ x 1 }`).
+    pub compact_structs: bool,
+    /// Use [`LineEnding::detect`] to match the text being replaced.
+    pub line_ending: LineEnding,
+    /// The units the text will be read with. A quantity in any other unit is
+    /// a [`WriteError`], as it would read back as a string.
+    pub units: UnitRegistry,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            indent: Indent::Spaces(4),
+            key_colon: false,
+            separator: Separator::Whitespace,
+            max_width: 100,
+            compact_structs: false,
+            line_ending: LineEnding::Lf,
+            units: UnitRegistry::new(),
+        }
+    }
+}
+
+/// Why a tree can not be written as text that reads back equal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteError {
+    /// The variant name would not read back, such as an empty one or one
+    /// holding whitespace or punctuation.
+    InvalidVariantName(String),
+    /// The payload of the named variant is not a tuple, struct or array,
+    /// the only values that can follow a variant name.
+    InvalidVariantPayload(String),
+    /// The quantity, as written, would not read back equal, usually because
+    /// its unit is not in [`WriteOptions::units`].
+    InvalidQuantity(String),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidVariantName(name) => write!(f, "`{name}` is not a valid variant name"),
+            Self::InvalidVariantPayload(name) => write!(
+                f,
+                "the payload of `:{name}` must be a tuple, struct or array"
+            ),
+            Self::InvalidQuantity(text) => {
+                write!(f, "`{text}` would not read back as the same quantity")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// Where a value is written. Bare words are read differently depending on the
+/// surrounding syntax, so quoting decisions depend on it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Field,
+    Array,
+    Tuple,
+}
+
+/// Writes the fields of `root` as a top-level yini document.
+///
+/// # Errors
+///
+/// Fails if `root` holds a value that would not read back equal.
+pub fn write_struct(root: &Struct) -> Result<String, WriteError> {
+    write_struct_with(root, &WriteOptions::default())
+}
+
+/// Writes the fields of `root` as a top-level yini document using `options`.
+///
+/// # Errors
+///
+/// Fails if `root` holds a value that would not read back equal.
+pub fn write_struct_with(root: &Struct, options: &WriteOptions) -> Result<String, WriteError> {
+    root.values()
+        .try_for_each(|value| check(value, &options.units))?;
+    let mut writer = Writer::new(options);
+    writer.write_fields(root, 0);
+    Ok(writer.out)
+}
+
+/// Writes a single value as it would appear after a key.
+///
+/// # Errors
+///
+/// Fails if `value` is or holds a value that would not read back equal.
+pub fn write_value(value: &Value) -> Result<String, WriteError> {
+    write_value_with(value, &WriteOptions::default())
+}
+
+/// Writes a single value as it would appear after a key using `options`.
+///
+/// # Errors
+///
+/// Fails if `value` is or holds a value that would not read back equal.
+pub fn write_value_with(value: &Value, options: &WriteOptions) -> Result<String, WriteError> {
+    check(value, &options.units)?;
+    let mut writer = Writer::new(options);
+    writer.write_value(value, Context::Field, 0);
+    Ok(writer.out)
+}
+
+/// Checks that `value` and everything in it can be written so that it reads
+/// back equal.
+fn check(value: &Value, units: &UnitRegistry) -> Result<(), WriteError> {
+    match value {
+        Value::Variant(name, payload) => {
+            if !crate::is_variant_name(name) {
+                return Err(WriteError::InvalidVariantName(name.clone()));
+            }
+            match payload.as_deref() {
+                None => Ok(()),
+                Some(payload @ (Value::Tuple(_) | Value::Struct(_) | Value::Array(_))) => {
+                    check(payload, units)
+                }
+                Some(_) => Err(WriteError::InvalidVariantPayload(name.clone())),
+            }
+        }
+        Value::Quantity(q) => {
+            let text = q.to_string();
+            match crate::parse_number(&text, units) {
+                Ok(Value::Quantity(back)) if back == *q => Ok(()),
+                _ => Err(WriteError::InvalidQuantity(text)),
+            }
+        }
+        Value::Struct(fields) => fields.values().try_for_each(|value| check(value, units)),
+        Value::Array(items) | Value::Tuple(items) => {
+            items.iter().try_for_each(|value| check(value, units))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `s` as a quoted string literal.
+pub(crate) fn quote(s: &str) -> String {
+    let options = WriteOptions::default();
+    let mut writer = Writer::new(&options);
+    writer.write_quoted(s);
+    writer.out
+}
+
+/// `bytes` as a `b64"..."` literal, padded with `=`.
+pub(crate) fn base64_literal(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4 + 5);
+    out.push_str("b64\"");
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0_u32, |group, (index, b)| {
+            group | u32::from(*b) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                out.push(char::from(
+                    DIGITS[(group >> (18 - 6 * index)) as usize & 63],
+                ));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `bytes` as a `hex"..."` literal.
+pub(crate) fn hex_literal(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2 + 5);
+    out.push_str("hex\"");
+    for b in bytes {
+        let _ = write!(out, "{b:02x}");
+    }
+    out.push('"');
+    out
+}
+
+struct Writer<'a> {
+    options: &'a WriteOptions,
+    out: String,
+    line_start: usize,
+}
+
+impl<'a> Writer<'a> {
+    const fn new(options: &'a WriteOptions) -> Self {
+        Self {
+            options,
+            out: String::new(),
+            line_start: 0,
+        }
+    }
+
+    fn write_fields(&mut self, fields: &Struct, depth: usize) {
+        for (key, value) in fields {
+            self.write_indent(depth);
+            self.write_field(key, value, depth);
+            self.newline();
+        }
+    }
+
+    fn write_field(&mut self, key: &str, value: &Value, depth: usize) {
+        self.write_key(key);
+        if self.options.key_colon {
+            self.out.push(':');
+        }
+        self.out.push(' ');
+        self.write_value(value, Context::Field, depth);
+    }
+
+    fn write_key(&mut self, key: &str) {
+        if is_bare_key(key) {
+            self.out.push_str(key);
+        } else {
+            self.write_quoted(key);
+        }
+    }
+
+    fn write_value(&mut self, value: &Value, context: Context, depth: usize) {
+        match value {
+            Value::Null => self.out.push_str("null"),
+            Value::Str(s) => {
+                if context != Context::Tuple && is_bare_word(s) {
+                    self.out.push_str(s);
+                } else {
+                    self.write_quoted(s);
+                }
+            }
+            Value::Bytes(bytes) => self.out.push_str(&base64_literal(bytes)),
+            Value::Int(i) => self.out.push_str(&i.to_string()),
+            Value::WideInt(i) => self.out.push_str(&i.to_string()),
+            Value::Num(n) => self.write_num(*n),
+            Value::Bool(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            Value::DateTime(d) => self.out.push_str(&d.to_string()),
+            Value::Duration(d) => self.out.push_str(&crate::datetime::format_duration(*d)),
+            Value::Quantity(q) => self.out.push_str(&q.to_string()),
+            Value::Variant(name, payload) => {
+                self.out.push(':');
+                self.out.push_str(name);
+                // The payload must touch the variant name, and `check` has made
+                // sure it is a container
+                match payload.as_deref() {
+                    Some(Value::Tuple(items)) => self.write_tuple(items, depth),
+                    Some(Value::Struct(fields)) => self.write_struct_block(fields, depth),
+                    Some(Value::Array(items)) => self.write_array(items, depth),
+                    _ => {}
+                }
+            }
+            Value::Struct(fields) => self.write_struct_block(fields, depth),
+            Value::Array(items) => self.write_array(items, depth),
+            Value::Tuple(items) => self.write_tuple(items, depth),
+        }
+    }
+
+    fn write_struct_block(&mut self, fields: &Struct, depth: usize) {
+        if fields.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+        if let Some((key, value)) = self.compact_field(fields) {
+            self.out.push_str("{ ");
+            self.write_field(key, value, depth);
+            self.out.push_str(" }");
+            return;
+        }
+        self.out.push('{');
+        self.newline();
+        self.write_fields(fields, depth + 1);
+        self.write_indent(depth);
+        self.out.push('}');
+    }
+
+    fn write_array(&mut self, items: &[Value], depth: usize) {
+        if items.iter().any(|item| self.is_block(item)) {
+            self.out.push('[');
+            self.newline();
+            for item in items {
+                self.write_indent(depth + 1);
+                self.write_value(item, Context::Array, depth + 1);
+                self.newline();
+            }
+            self.write_indent(depth);
+            self.out.push(']');
+            return;
+        }
+
+        let start = self.out.len();
+        self.out.push('[');
+        self.write_inline_items(items, Context::Array, depth);
+        self.out.push(']');
+        if self.column() <= self.options.max_width || !items.iter().all(is_scalar) {
+            return;
+        }
+
+        // Too wide, so fill lines with as many scalars as fit
+        self.out.truncate(start);
+        self.out.push('[');
+        self.newline();
+        self.write_indent(depth + 1);
+        let mut line_has_item = false;
+        for (index, item) in items.iter().enumerate() {
+            let item_start = self.out.len();
+            if line_has_item {
+                self.out.push(' ');
+            }
+            self.write_value(item, Context::Array, depth + 1);
+            if index + 1 < items.len() && self.options.separator == Separator::Comma {
+                self.out.push(',');
+            }
+            if line_has_item && self.column() > self.options.max_width {
+                let text = self.out.split_off(item_start);
+                self.newline();
+                self.write_indent(depth + 1);
+                self.out.push_str(text.trim_start());
+            }
+            line_has_item = true;
+        }
+        self.newline();
+        self.write_indent(depth);
+        self.out.push(']');
+    }
+
+    fn write_tuple(&mut self, items: &[Value], depth: usize) {
+        self.out.push('(');
+        if self.is_multiline_tuple(items) {
+            self.newline();
+            for item in items {
+                self.write_indent(depth + 1);
+                self.write_value(item, Context::Tuple, depth + 1);
+                self.newline();
+            }
+            self.write_indent(depth);
+        } else {
+            self.write_inline_items(items, Context::Tuple, depth);
+        }
+        self.out.push(')');
+    }
+
+    fn write_inline_items(&mut self, items: &[Value], context: Context, depth: usize) {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.out.push_str(match self.options.separator {
+                    Separator::Whitespace => " ",
+                    Separator::Comma => ", ",
+                });
+            }
+            self.write_value(item, context, depth);
+        }
+    }
+
+    fn write_num(&mut self, n: f64) {
+        if n.is_nan() {
+            self.out.push_str("nan");
+            return;
+        }
+        if n.is_infinite() {
+            self.out.push_str(if n > 0.0 { "inf" } else { "-inf" });
+            return;
+        }
+        let text = n.to_string();
+        self.out.push_str(&text);
+        // Numbers without a fraction would read back as integers
+        if !text.contains('.') {
+            self.out.push_str(".0");
+        }
+    }
+
+    fn write_quoted(&mut self, s: &str) {
+        self.out.reserve(s.len() + 2);
+        self.out.push('"');
+        for ch in s.chars() {
+            match ch {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\t' => self.out.push_str("\\t"),
+                '\r' => self.out.push_str("\\r"),
+                '\0' => self.out.push_str("\\0"),
+                other if other.is_control() => {
+                    let _ = write!(self.out, "\\u{{{:x}}}", u32::from(other));
+                }
+                other => self.out.push(other),
+            }
+        }
+        self.out.push('"');
+    }
+
+    fn write_indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            match self.options.indent {
+                Indent::Spaces(width) => {
+                    for _ in 0..width {
+                        self.out.push(' ');
+                    }
+                }
+                Indent::Tab => self.out.push('\t'),
+            }
+        }
+    }
+
+    fn newline(&mut self) {
+        self.out.push_str(self.options.line_ending.as_str());
+        self.line_start = self.out.len();
+    }
+
+    fn column(&self) -> usize {
+        self.out[self.line_start..].chars().count()
+    }
+
+    /// The only field of a struct that can be written on a single line.
+    fn compact_field<'v>(&self, fields: &'v Struct) -> Option<(&'v String, &'v Value)> {
+        if !self.options.compact_structs || fields.len() != 1 {
+            return None;
+        }
+        fields
+            .iter()
+            .next()
+            .filter(|(_, value)| !self.is_block(value))
+    }
+
+    fn is_multiline_tuple(&self, items: &[Value]) -> bool {
+        // A bare word inside a tuple runs until `)` or end of line, so anything
+        // following one has to start on a new line. `inf` and `nan` are bare
+        // words there too, while `-inf` reads as a number.
+        items.iter().any(|item| self.is_block(item))
+            || items.iter().rev().skip(1).any(|item| match item {
+                Value::Bool(_) | Value::Null => true,
+                Value::Num(n) => n.is_nan() || *n == f64::INFINITY,
+                _ => false,
+            })
+    }
+
+    /// Values that are written over several lines.
+    fn is_block(&self, value: &Value) -> bool {
+        match value {
+            Value::Struct(fields) => !fields.is_empty() && self.compact_field(fields).is_none(),
+            Value::Array(items) => items.iter().any(|item| self.is_block(item)),
+            Value::Tuple(items) => self.is_multiline_tuple(items),
+            Value::Variant(_, Some(payload)) => self.is_block(payload),
+            _ => false,
+        }
+    }
+}
+
+const fn is_scalar(value: &Value) -> bool {
+    !matches!(
+        value,
+        Value::Struct(_) | Value::Array(_) | Value::Tuple(_) | Value::Variant(_, Some(_))
+    )
+}
+
+/// A string that can be written without quotes and still reads back as the same string.
+fn is_bare_word(s: &str) -> bool {
+    crate::is_identifier(s) && crate::keyword(s).is_none()
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+}
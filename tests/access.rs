@@ -0,0 +1,209 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use std::time::Duration;
+
+use yini::access::{LookupError, LookupErrorKind, Spanned};
+use yini::path::PathErrorKind;
+use yini::{FromValue, Lookup, Parser, Struct, Value};
+
+#[test]
+fn typed_values() {
+    let data = r"
+server {
+    host localhost
+    port 8080
+    timeout 250ms
+}
+workers [{ name a } { name b }]
+origin (1.5 2)
+";
+    let root = Parser::new(data).parse();
+    assert_eq!(root.req::<u16>("server.port").unwrap(), 8080);
+    assert_eq!(root.req::<&str>("server.host").unwrap(), "localhost");
+    assert_eq!(
+        root.req::<Duration>("server.timeout").unwrap(),
+        Duration::from_millis(250)
+    );
+    assert_eq!(root.req::<(f64, f64)>("origin").unwrap(), (1.5, 2.0));
+    assert_eq!(root.req::<Vec<&Struct>>("workers").unwrap().len(), 2);
+}
+
+#[test]
+fn integers_promote_to_floats() {
+    let root = Parser::new("server {\n    ratio 2\n}\n").parse();
+    assert_eq!(root.req::<f64>("server.ratio").unwrap(), 2.0);
+    assert_eq!(root.req::<f32>("server.ratio").unwrap(), 2.0);
+
+    let root = Parser::new("big 9007199254740993\n").parse();
+    let error = root.req::<f64>("big").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "9007199254740993 does not fit in f64 at `big`"
+    );
+}
+
+#[test]
+fn narrowing_is_range_checked() {
+    let data = r"
+server {
+    port 8080
+}
+workers [{ threads 4 } { threads -1 }]
+";
+    let root = Parser::new(data).parse();
+    let error = root.req::<u32>("workers[1].threads").unwrap_err();
+    assert_eq!(
+        error.kind,
+        LookupErrorKind::OutOfRange {
+            value: "-1".to_string(),
+            target: "u32",
+        }
+    );
+    assert_eq!(error.path, "workers[1].threads");
+    assert_eq!(root.req::<i8>("workers[1].threads").unwrap(), -1);
+    assert!(root.req::<u8>("server.port").is_err());
+}
+
+#[test]
+fn optional_values_and_defaults() {
+    let data = r#"
+server {
+    host "localhost"
+    port 8080
+    proxy null
+}
+"#;
+    let root = Parser::new(data).parse();
+    assert_eq!(
+        root.opt::<String>("server.host").unwrap().as_deref(),
+        Some("localhost")
+    );
+    assert_eq!(root.opt::<String>("server.proxy").unwrap(), None);
+    assert_eq!(root.opt::<String>("client.host").unwrap(), None);
+    assert_eq!(root.get_or("server.threads", 4).unwrap(), 4);
+    assert_eq!(root.get_or("server.port", 80).unwrap(), 8080);
+    // A value of the wrong type is still an error
+    assert!(root.get_or("server.host", 80).is_err());
+}
+
+#[test]
+fn errors_name_path_and_types() {
+    let data = r#"
+server {
+    host "localhost"
+    port 8080
+}
+workers [{ threads 4 }]
+origin (1.5 2)
+"#;
+    let root = Parser::new(data).parse();
+
+    let error = root.req::<u16>("server.host").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected an integer, found a string at `server.host`"
+    );
+
+    let error = root.req::<u16>("server.missing").unwrap_err();
+    assert_eq!(error.kind, LookupErrorKind::Missing);
+    assert_eq!(error.to_string(), "missing value at `server.missing`");
+
+    let error = root.req::<u16>("server.port.value").unwrap_err();
+    assert_eq!(
+        error.kind,
+        LookupErrorKind::Path(PathErrorKind::WrongKind {
+            expected: "a struct",
+            found: "an integer",
+        })
+    );
+
+    let error = root.req::<Vec<u8>>("workers").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected an integer, found a struct at `workers[0]`"
+    );
+
+    let error = root.req::<(f64, f64, f64)>("origin").unwrap_err();
+    assert_eq!(error.to_string(), "expected 3 items, found 2 at `origin`");
+}
+
+#[test]
+fn spanned_errors_carry_the_location() {
+    let data = r#"
+server {
+    host "localhost"
+    port 8080
+}
+workers [
+    { threads 4 }
+    { threads -1 }
+]
+"#;
+    let (root, spans) = Parser::new(data).parse_spanned();
+    let config = Spanned::new(&root, &spans);
+    assert_eq!(config.req::<u16>("server.port").unwrap(), 8080);
+
+    let error = config.req::<u16>("server.host").unwrap_err();
+    let position = error.position.unwrap();
+    assert_eq!((position.line, position.column), (3, 10));
+    assert_eq!(
+        error.to_string(),
+        "expected an integer, found a string at `server.host`, line 3, column 10"
+    );
+
+    let error = config.req::<u32>("workers.1.threads").unwrap_err();
+    assert_eq!(error.position.unwrap().line, 8);
+
+    // A missing value points at the struct it is missing from
+    let error = config.req::<u16>("server.missing").unwrap_err();
+    assert_eq!(error.position.unwrap().line, 2);
+}
+
+#[derive(Debug)]
+struct Worker {
+    name: String,
+    threads: u8,
+}
+
+impl FromValue<'_> for Worker {
+    fn from_value(value: &Value) -> Result<Self, LookupError> {
+        Ok(Self {
+            name: value.req("name")?,
+            threads: value.req("threads")?,
+        })
+    }
+}
+
+#[test]
+fn custom_types_prefix_nested_errors() {
+    let data = r"
+workers [
+    {
+        name a
+        threads 4
+    }
+    {
+        name b
+        threads -1
+    }
+]
+";
+    let root = Parser::new(data).parse();
+    let worker = root.req::<Worker>("workers[0]").unwrap();
+    assert_eq!((worker.name.as_str(), worker.threads), ("a", 4));
+
+    let error = root.req::<Vec<Worker>>("workers").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "-1 does not fit in u8 at `workers[1].threads`"
+    );
+
+    let (root, spans) = Parser::new(data).parse_spanned();
+    let error = Spanned::new(&root, &spans)
+        .req::<Vec<Worker>>("workers")
+        .unwrap_err();
+    assert_eq!(error.position.unwrap().line, 9);
+}
@@ -0,0 +1,134 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use std::time::Duration;
+
+use yini::{Parser, Struct, Value, write_struct, yini};
+
+#[test]
+fn macro_matches_parsed_text() {
+    let port: u16 = 8080;
+    let built = yini! {
+        name: "Main",
+        "log level": 3,
+        server: {
+            port: port,
+            ratio: 1.5,
+            hosts: ["alpha", "beta",],
+        },
+        origin: (0, -1),
+        screen: :fullscreen(1024, 768),
+        window: :windowed{ width: 800 },
+        modes: [:low, :high],
+        enabled: true,
+        proxy: null,
+        timeout: Duration::from_millis(250),
+    };
+    let parsed = Parser::new(
+        r#"
+name Main
+"log level" 3
+server {
+    port 8080
+    ratio 1.5
+    hosts [alpha beta]
+}
+origin (0 -1)
+screen :fullscreen(1024 768)
+window :windowed{
+    width 800
+}
+modes [:low :high]
+enabled true
+proxy null
+timeout 250ms
+"#,
+    )
+    .parse();
+    assert_eq!(Value::Struct(built), Value::Struct(parsed));
+}
+
+#[test]
+fn macro_with_long_literal() {
+    let built = yini! {
+        items: [
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19,
+            20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39,
+            40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59,
+            60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79,
+        ],
+        offset: -(2 * 3),
+    };
+    let expected: Vec<Value> = (0..80).map(Value::from).collect();
+    assert_eq!(built.get("items"), Some(&Value::Array(expected)));
+    assert_eq!(built.get("offset"), Some(&Value::from(-6)));
+}
+
+#[test]
+fn macro_with_empty_containers() {
+    let built = yini! {
+        empty: {},
+        items: [],
+    };
+    assert_eq!(built.get("empty"), Some(&Value::Struct(Struct::new())));
+    assert_eq!(built.get("items"), Some(&Value::Array(Vec::new())));
+    assert!(yini! {}.is_empty());
+}
+
+#[test]
+fn from_conversions() {
+    assert_eq!(Value::from(true), Value::Bool(true));
+    assert_eq!(Value::from(7u8), Value::Int(7));
+    assert_eq!(Value::from(u64::MAX), Value::WideInt(u64::MAX.into()));
+    assert_eq!(Value::from(3usize), Value::Int(3));
+    assert_eq!(Value::from(0.5f32), Value::Num(0.5));
+    assert_eq!(Value::from("hi"), Value::Str("hi".to_string()));
+    assert_eq!(Value::from(None::<i32>), Value::Null);
+    assert_eq!(
+        Value::from(vec![1, 2]),
+        Value::Array(vec![Value::Int(1), Value::Int(2)])
+    );
+    assert_eq!(
+        Value::from((1, "a")),
+        Value::Tuple(vec![Value::Int(1), Value::Str("a".to_string())])
+    );
+}
+
+#[test]
+fn modify_in_place_and_write() {
+    let mut root = Value::Struct(yini! {
+        server: {
+            port: 80,
+            hosts: ["alpha"],
+        },
+        point: (1, 2),
+    });
+    let server = root
+        .as_struct_mut()
+        .and_then(|root| root.get_mut(&"server".to_string()))
+        .and_then(Value::as_struct_mut)
+        .unwrap();
+    let old = server.get_mut(&"port".to_string()).unwrap().replace(8080);
+    assert_eq!(old, Value::Int(80));
+    server
+        .get_mut(&"hosts".to_string())
+        .and_then(Value::as_array_mut)
+        .unwrap()
+        .push("beta".into());
+
+    let Value::Struct(fields) = &mut root else {
+        unreachable!()
+    };
+    let point = fields.get_mut(&"point".to_string()).unwrap();
+    point.as_tuple_mut().unwrap()[1] = Value::Int(5);
+    let taken = point.take();
+    assert!(point.is_null());
+    assert_eq!(taken, Value::from((1, 5)));
+
+    assert_eq!(
+        write_struct(root.as_struct().unwrap()).unwrap(),
+        "server {\n    port 8080\n    hosts [alpha beta]\n}\npoint null\n"
+    );
+}
@@ -0,0 +1,73 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+#![cfg(feature = "chrono")]
+
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use yini::Value;
+use yini::datetime::DateTime;
+
+fn parse_date(text: &str) -> DateTime {
+    let root = yini::parse_str(&format!("at {text}\n")).unwrap();
+    *root.get("at").and_then(Value::as_datetime).unwrap()
+}
+
+#[test]
+fn converts_to_chrono() {
+    let date = NaiveDate::try_from(parse_date("2024-05-01")).unwrap();
+    assert_eq!(date, NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+
+    let local = NaiveDateTime::try_from(parse_date("2024-05-01T12:30:15.5")).unwrap();
+    assert_eq!(local, date.and_hms_milli_opt(12, 30, 15, 500).unwrap());
+
+    let offset =
+        chrono::DateTime::<FixedOffset>::try_from(parse_date("2024-05-01T12:30:00+02:00")).unwrap();
+    let expected = FixedOffset::east_opt(7200)
+        .unwrap()
+        .with_ymd_and_hms(2024, 5, 1, 12, 30, 0)
+        .unwrap();
+    assert_eq!(offset, expected);
+
+    assert!(NaiveDateTime::try_from(parse_date("2024-05-01")).is_err());
+    assert!(chrono::DateTime::<FixedOffset>::try_from(parse_date("2024-05-01T12:30:00")).is_err());
+}
+
+#[test]
+fn converts_from_chrono() {
+    let expected = FixedOffset::west_opt(5400)
+        .unwrap()
+        .with_ymd_and_hms(1999, 12, 31, 23, 59, 59)
+        .unwrap();
+    let date_time = DateTime::try_from(expected).unwrap();
+    assert_eq!(date_time.to_string(), "1999-12-31T23:59:59-01:30");
+    assert_eq!(
+        chrono::DateTime::<FixedOffset>::try_from(date_time).unwrap(),
+        expected
+    );
+    let leap = NaiveDate::from_ymd_opt(2016, 12, 31)
+        .unwrap()
+        .and_hms_milli_opt(23, 59, 59, 1_000)
+        .unwrap();
+    let date_time = DateTime::try_from(leap).unwrap();
+    assert_eq!(date_time.to_string(), "2016-12-31T23:59:60");
+    assert_eq!(NaiveDateTime::try_from(date_time).unwrap(), leap);
+}
+
+#[test]
+fn rejects_chrono_values_that_can_not_be_written() {
+    let date = NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+    assert_eq!(
+        parse_date(&DateTime::try_from(date).unwrap().to_string()),
+        DateTime::try_from(date).unwrap()
+    );
+    assert!(DateTime::try_from(NaiveDate::from_ymd_opt(10_000, 1, 1).unwrap()).is_err());
+    assert!(DateTime::try_from(NaiveDate::from_ymd_opt(-1, 1, 1).unwrap()).is_err());
+
+    let odd_offset = FixedOffset::east_opt(30)
+        .unwrap()
+        .with_ymd_and_hms(2024, 5, 1, 12, 0, 0)
+        .unwrap();
+    assert!(DateTime::try_from(odd_offset).is_err());
+}
@@ -0,0 +1,152 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use yini::Parser;
+use yini::cst::{CstValue, TriviaKind};
+
+const DATA: &str = r#"# Game settings
+# (edited by hand)
+
+name:   "Main"   # display name
+description this is a long description
+server {
+    host localhost
+    "port number" 8080
+
+    # remember to enable in production
+    ssl false
+}
+tags [fast "very safe"
+    # more later
+]
+pair (1 two words)
+modes [:fullscreen :windowed(800 600) :custom{ w 1 }]
+# trailing comment"#;
+
+fn round_trip(text: &str) {
+    let mut parser = Parser::new(text);
+    let tree = parser.parse_cst();
+    assert_eq!(tree.to_string(), text);
+}
+
+#[test]
+fn prints_back_identical() {
+    let mut parser = Parser::new(DATA);
+    let tree = parser.parse_cst();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    assert_eq!(tree.to_string(), DATA);
+
+    let mut parser = Parser::new(DATA);
+    let root = parser.parse();
+    assert_eq!(tree.to_struct().len(), root.len());
+    for ((a_key, a), (b_key, b)) in tree.to_struct().iter().zip(root.iter()) {
+        assert_eq!(a_key, b_key);
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+fn keeps_comments_colons_and_quoting() {
+    let tree = Parser::new(DATA).parse_cst();
+    let name = tree.root.field("name").unwrap();
+    assert!(name.colon);
+    assert!(name.leading.iter().any(|t| t.text == "# (edited by hand)"));
+    assert_eq!(name.trailing.last().unwrap().kind, TriviaKind::Newline);
+    assert!(
+        name.trailing
+            .iter()
+            .any(|t| t.kind == TriviaKind::Comment && t.text == "# display name")
+    );
+    let CstValue::Scalar(scalar) = &name.value else {
+        panic!("name should be a scalar");
+    };
+    assert!(scalar.is_quoted());
+    assert_eq!(scalar.raw, "\"Main\"");
+
+    let CstValue::Struct(server) = &tree.root.field("server").unwrap().value else {
+        panic!("server should be a struct");
+    };
+    assert!(server.closed);
+    let port = server.field("port number").unwrap();
+    assert_eq!(port.key.raw, "\"port number\"");
+    assert!(!port.colon);
+    let ssl = server.field("ssl").unwrap();
+    assert!(
+        ssl.leading
+            .iter()
+            .any(|t| t.text == "# remember to enable in production")
+    );
+}
+
+#[test]
+fn round_trips_odd_layouts() {
+    round_trip("");
+    round_trip("\n\n   \n");
+    round_trip("# only a comment");
+    round_trip("a 1\r\nb 2\r\n");
+    round_trip("\u{feff}# windows\r\na [1,\r\n  2]\r\nb 2\rc 3");
+    round_trip("\tindented\t\"tabs\"\t# and a comment\n");
+    round_trip("empty {}\nlist []\nnothing ()\n");
+    round_trip("nested [[1 2] [3 [4]]]\n");
+    round_trip("pos { x 1 }\n");
+    round_trip("text \"\"\"\n    a \\\n    b\n    \"\"\"\nraw [r#\"\\d\"#, r\"x\"]\n");
+}
+
+#[test]
+fn round_trips_input_with_errors() {
+    round_trip("a 1\nb\nc 3\na 4\n");
+    round_trip("} stray\nx 1\n");
+    round_trip("open {\n    a 1\n");
+    round_trip("list [1 2\n");
+    round_trip("s \"unterminated\n");
+    round_trip("t (1 (2)");
+    round_trip("k {)");
+    round_trip("k {\n  a 1\n]");
+
+    let mut parser = Parser::new("a 1\na 2\n");
+    let tree = parser.parse_cst();
+    let skipped: Vec<_> = tree
+        .root
+        .trailing
+        .iter()
+        .filter(|t| t.kind == TriviaKind::Skipped)
+        .map(|t| t.text.as_str())
+        .collect();
+    assert_eq!(skipped, ["a", "2"]);
+}
+
+#[test]
+fn line_breaks_are_single_newline_trivia() {
+    let tree = Parser::new("a 1 # one\r\nb 2\rc 3\n").parse_cst();
+    let texts = |name: &str| -> Vec<(TriviaKind, String)> {
+        let field = tree.root.field(name).unwrap();
+        field
+            .trailing
+            .iter()
+            .map(|t| (t.kind, t.text.clone()))
+            .collect()
+    };
+    assert_eq!(
+        texts("a"),
+        [
+            (TriviaKind::Whitespace, " ".to_string()),
+            (TriviaKind::Comment, "# one".to_string()),
+            (TriviaKind::Newline, "\r\n".to_string()),
+        ]
+    );
+    assert_eq!(texts("b"), [(TriviaKind::Newline, "\r".to_string())]);
+}
+
+#[test]
+fn multiline_and_raw_strings_count_as_quoted() {
+    let tree = Parser::new("a \"\"\"\n  x\n  \"\"\"\nb r#\"y\"#\nc r\n").parse_cst();
+    let quoted = |name: &str| match &tree.root.field(name).unwrap().value {
+        CstValue::Scalar(scalar) => scalar.is_quoted(),
+        _ => panic!("{name} should be a scalar"),
+    };
+    assert!(quoted("a"));
+    assert!(quoted("b"));
+    assert!(!quoted("c"));
+}
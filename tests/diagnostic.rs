@@ -0,0 +1,89 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use yini::{DiagnosticOptions, Parser, render_diagnostics, render_diagnostics_with};
+
+fn diagnostics(source: &str, options: &DiagnosticOptions) -> String {
+    let mut parser = Parser::new(source);
+    let _ = parser.parse();
+    render_diagnostics_with(source, parser.errors(), options)
+}
+
+#[test]
+fn renders_snippet_with_caret_and_hint() {
+    let options = DiagnosticOptions {
+        color: false,
+        origin: Some("game.yini".to_string()),
+    };
+    assert_eq!(
+        diagnostics("name \"Main\"\nhost\n", &options),
+        "\
+error: expected a value on the same line as the key
+ --> game.yini:2:5
+  |
+2 | host
+  |     ^
+  = help: values must start on the same line as the key
+"
+    );
+}
+
+#[test]
+fn underlines_whole_number() {
+    let source = "a 1\nb 2\nc 3\nd 4\ne 5\nf 6\ng 7\nh 8\ni 9\nport 9999999999999999999999999999999999999999\n";
+    assert_eq!(
+        diagnostics(source, &DiagnosticOptions::default()),
+        "\
+error: `9999999999999999999999999999999999999999` is not a valid integer
+  --> 10:46
+   |
+10 | port 9999999999999999999999999999999999999999
+   |      ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+   = help: integers use the digits of their base, with `_` only between digits, and fit in 128 bits
+"
+    );
+}
+
+#[test]
+fn malformed_digits_get_the_integer_hint() {
+    assert_eq!(
+        diagnostics("mask 0xFG\n", &DiagnosticOptions::default()),
+        "\
+error: `0xFG` is not a valid integer
+ --> 1:10
+  |
+1 | mask 0xFG
+  |      ^^^^
+  = help: integers use the digits of their base, with `_` only between digits, and fit in 128 bits
+"
+    );
+}
+
+#[test]
+fn keeps_tabs_and_points_at_string_start() {
+    let source = "\tpair (1 2) x\ns \"open\n";
+    let mut parser = Parser::new(source);
+    let _ = parser.parse();
+    let rendered = render_diagnostics(source, parser.errors());
+    assert!(
+        rendered.contains("1 | \tpair (1 2) x\n  | \t           ^\n"),
+        "{rendered}"
+    );
+    assert!(rendered.contains("2 | s \"open\n  |   ^\n"), "{rendered}");
+    assert_eq!(rendered.matches("error: ").count(), parser.errors().len());
+}
+
+#[test]
+fn colors_only_when_asked() {
+    let colored = diagnostics(
+        "host\n",
+        &DiagnosticOptions {
+            color: true,
+            origin: None,
+        },
+    );
+    assert!(colored.starts_with("\x1b[1;31merror\x1b[0m"));
+    assert!(!diagnostics("host\n", &DiagnosticOptions::default()).contains('\x1b'));
+}
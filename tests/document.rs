@@ -0,0 +1,255 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use seq_map::SeqMap;
+use yini::Value;
+use yini::document::{Document, EditError};
+
+const DATA: &str = r#"# Deployment settings
+
+name "Main"   # shown in the lobby
+
+server {
+    # public port
+    port 8080
+    hosts [alpha beta]
+}
+
+modes [
+    :fullscreen
+    :windowed(800 600)
+]
+"#;
+
+fn doc() -> Document {
+    Document::parse(DATA).unwrap()
+}
+
+#[test]
+fn untouched_document_prints_back_identical() {
+    assert_eq!(doc().to_string(), DATA);
+}
+
+#[test]
+fn set_replaces_value_and_keeps_comments() {
+    let mut doc = doc();
+    doc.set("server.port", Value::Int(9090)).unwrap();
+    doc.set("name", Value::Str("Other".to_string())).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        DATA.replace("8080", "9090")
+            .replace("\"Main\"", "\"Other\"")
+    );
+    assert_eq!(doc.get("server.port"), Some(Value::Int(9090)));
+}
+
+#[test]
+fn set_inserts_new_fields() {
+    let mut doc = doc();
+    doc.set("server.threads", Value::Int(4)).unwrap();
+    doc.set("limits.players", Value::Int(16)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        DATA.replace("beta]\n", "beta]\n    threads 4\n") + "limits {\n    players 16\n}\n"
+    );
+}
+
+#[test]
+fn set_opens_up_empty_and_single_line_structs() {
+    let mut doc = Document::parse("a {}\nb { x 1 }\n").unwrap();
+    doc.set("a.x", Value::Int(1)).unwrap();
+    doc.set("b.y", Value::Int(2)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "a {\n    x 1\n}\nb {\n    x 1\n    y 2\n}\n"
+    );
+}
+
+#[test]
+fn new_values_follow_document_indentation() {
+    let mut doc = Document::parse("server {\n\tport 1\n}\n").unwrap();
+    let mut limits = SeqMap::new();
+    limits.insert("max".to_string(), Value::Int(3)).unwrap();
+    doc.set("server.limits", Value::Struct(limits)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "server {\n\tport 1\n\tlimits {\n\t\tmax 3\n\t}\n}\n"
+    );
+}
+
+#[test]
+fn push_appends_to_arrays() {
+    let mut doc = doc();
+    doc.push("server.hosts", Value::Str("gamma".to_string()))
+        .unwrap();
+    doc.push("modes", Value::Variant("headless".to_string(), None))
+        .unwrap();
+    assert_eq!(
+        doc.to_string(),
+        DATA.replace("beta]", "beta \"gamma\"]")
+            .replace("600)\n", "600)\n    :headless\n")
+    );
+
+    let mut doc = Document::parse("list []\n").unwrap();
+    doc.push("list", Value::Int(1)).unwrap();
+    doc.push("list", Value::Int(2)).unwrap();
+    assert_eq!(doc.to_string(), "list [1 2]\n");
+}
+
+#[test]
+fn push_keeps_comma_separators() {
+    let mut doc = Document::parse("a [1, 2]\nb [\n    1,\n    2\n]\n").unwrap();
+    doc.push("a", Value::Int(3)).unwrap();
+    doc.push("b", Value::Int(3)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "a [1, 2, 3]\nb [\n    1,\n    2,\n    3\n]\n"
+    );
+}
+
+#[test]
+fn remove_takes_attached_comments() {
+    let mut doc = doc();
+    assert_eq!(doc.remove("server.port"), Some(Value::Int(8080)));
+    assert_eq!(doc.remove("name").unwrap().as_str(), Some("Main"));
+    assert_eq!(
+        doc.to_string(),
+        "# Deployment settings\n\nserver {\n    hosts [alpha beta]\n}\n\nmodes [\n    :fullscreen\n    :windowed(800 600)\n]\n"
+    );
+    assert_eq!(doc.remove("missing"), None);
+
+    assert!(doc.remove("modes.0").is_some());
+    assert!(doc.remove("server.hosts.1").is_some());
+    assert!(
+        doc.to_string()
+            .ends_with("server {\n    hosts [alpha]\n}\n\nmodes [\n    :windowed(800 600)\n]\n")
+    );
+}
+
+#[test]
+fn rename_variant_keeps_payload() {
+    let mut doc = doc();
+    doc.rename_variant("modes.1", "borderless").unwrap();
+    assert_eq!(doc.to_string(), DATA.replace(":windowed", ":borderless"));
+    assert_eq!(
+        doc.rename_variant("name", "x"),
+        Err(EditError::WrongKind {
+            path: "name".to_string(),
+            expected: "a variant",
+        })
+    );
+    for name in ["", "a b", "a:b", "a(b"] {
+        assert_eq!(
+            doc.rename_variant("modes.1", name),
+            Err(EditError::InvalidVariantName(name.to_string()))
+        );
+    }
+    assert_eq!(doc.to_string(), DATA.replace(":windowed", ":borderless"));
+}
+
+#[test]
+fn edits_through_variant_payloads_and_items() {
+    let mut doc = doc();
+    doc.set("modes.1.0", Value::Int(1024)).unwrap();
+    assert_eq!(doc.get("modes.1.0"), Some(Value::Int(1024)));
+    assert!(doc.to_string().contains(":windowed(1024 600)"));
+    assert!(doc.set("name.x", Value::Int(1)).is_err());
+    assert!(Document::parse("a\n").is_err());
+}
+
+#[test]
+fn edits_keep_crlf_line_endings() {
+    let text = DATA.replace('\n', "\r\n");
+    let mut doc = Document::parse(&text).unwrap();
+    assert_eq!(doc.to_string(), text);
+    doc.set("server.threads", Value::Int(4)).unwrap();
+    doc.set("limits.players", Value::Int(16)).unwrap();
+    doc.push("modes", Value::Variant("headless".to_string(), None))
+        .unwrap();
+    let expected = DATA
+        .replace("beta]\n", "beta]\n    threads 4\n")
+        .replace("600)\n", "600)\n    :headless\n")
+        + "limits {\n    players 16\n}\n";
+    assert_eq!(doc.to_string(), expected.replace('\n', "\r\n"));
+}
+
+#[test]
+fn set_keeps_integer_base() {
+    let mut doc = Document::parse("color 0xFF8000\nmask 0b0011\nids [0x0a, 7]\n").unwrap();
+    doc.set("color", Value::Int(0x00FF_00FF)).unwrap();
+    doc.set("mask", Value::Int(-5)).unwrap();
+    doc.set("ids.0", Value::Int(255)).unwrap();
+    doc.set("ids.1", Value::Int(8)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "color 0xFF00FF\nmask -0b101\nids [0xff, 8]\n"
+    );
+
+    let mut doc = Document::parse("seed 0x1F\n").unwrap();
+    doc.set("seed", Value::WideInt(i128::from(u64::MAX)))
+        .unwrap();
+    assert_eq!(doc.to_string(), "seed 0xFFFFFFFFFFFFFFFF\n");
+}
+
+#[test]
+fn set_keeps_hex_bytes() {
+    let mut doc = Document::parse("key hex\"00\" # secret\nicon b64\"AA==\"\n").unwrap();
+    doc.set("key", Value::Bytes(vec![0xAB, 0xCD])).unwrap();
+    doc.set("icon", Value::Bytes(vec![1])).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "key hex\"abcd\" # secret\nicon b64\"AQ==\"\n"
+    );
+}
+
+#[test]
+fn set_rejects_empty_keys() {
+    let mut doc = Document::parse("a 1\n").unwrap();
+    assert_eq!(
+        doc.set("", Value::Int(2)),
+        Err(EditError::EmptyKey(String::new()))
+    );
+    assert_eq!(
+        doc.set("b.", Value::Int(2)),
+        Err(EditError::EmptyKey("b.".to_string()))
+    );
+    assert_eq!(doc.to_string(), "a 1\n");
+}
+
+#[test]
+fn keywords_in_tuples_read_back() {
+    let reparse = |doc: &Document| Document::parse(&doc.to_string()).unwrap();
+
+    let mut doc = Document::parse("t (0 1) # pair\n").unwrap();
+    doc.set("t.0", Value::Bool(true)).unwrap();
+    assert_eq!(doc.to_string(), "t (\n    true\n    1\n) # pair\n");
+    assert_eq!(reparse(&doc).get("t"), doc.get("t"));
+
+    let mut doc = Document::parse("s {\n    t (1 2)\n}\n").unwrap();
+    doc.set("s.t.0", Value::Null).unwrap();
+    doc.set("s.t.1", Value::Num(f64::INFINITY)).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "s {\n    t (\n        null\n        inf\n    )\n}\n"
+    );
+    assert_eq!(reparse(&doc).get("s.t"), doc.get("s.t"));
+
+    let mut doc = Document::parse("t (1 true)\n").unwrap();
+    doc.push("t", Value::Int(2)).unwrap();
+    assert_eq!(
+        doc.get("t"),
+        Some(Value::Tuple(vec![
+            Value::Int(1),
+            Value::Bool(true),
+            Value::Int(2)
+        ]))
+    );
+    assert_eq!(reparse(&doc).get("t"), doc.get("t"));
+
+    // Arrays have no such problem and keep their layout
+    let mut doc = Document::parse("a [0 1]\n").unwrap();
+    doc.set("a.0", Value::Bool(false)).unwrap();
+    assert_eq!(doc.to_string(), "a [false 1]\n");
+}
@@ -0,0 +1,215 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use yini::path::PathErrorKind;
+use yini::{Parser, PathQuery, Value};
+
+#[test]
+fn dotted_keys_and_indices() {
+    let data = r"
+server {
+    port 8080
+    users [
+        {
+            name alice
+            roles [admin dev]
+        }
+        {
+            name bob
+            roles [dev]
+        }
+    ]
+}
+point (3 4)
+";
+    let root = Parser::new(data).parse();
+    assert_eq!(root.get_path("server.port").unwrap().as_int(), Some(8080));
+    assert_eq!(
+        root.get_path("server.users[1].name").unwrap().as_str(),
+        Some("bob")
+    );
+    assert_eq!(
+        root.get_path("server.users.0.roles[1]").unwrap().as_str(),
+        Some("dev")
+    );
+    assert_eq!(root.get_path("point.1").unwrap().as_int(), Some(4));
+}
+
+#[test]
+fn quoted_keys() {
+    let root = Parser::new("server {\n    \"log level\" debug\n}\n").parse();
+    assert_eq!(
+        root.get_path(r#"server."log level""#).unwrap().as_str(),
+        Some("debug")
+    );
+    assert_eq!(
+        root.get_path(r#"server["log level"]"#).unwrap().as_str(),
+        Some("debug")
+    );
+}
+
+#[test]
+fn variant_payloads() {
+    let root = Parser::new("screen :fullscreen(1024 768)\nmode :windowed\n").parse();
+    assert_eq!(
+        root.get_path("screen:fullscreen.0").unwrap().as_int(),
+        Some(1024)
+    );
+    assert_eq!(root.get_path("screen:*[1]").unwrap().as_int(), Some(768));
+
+    let error = root.get_path("screen:windowed.0").unwrap_err();
+    assert_eq!(error.at, "screen:windowed");
+    assert_eq!(error.segment, 1);
+    assert_eq!(
+        error.to_string(),
+        "expected variant `:windowed`, found `:fullscreen` at `screen:windowed`"
+    );
+
+    let error = root.get_path("mode:windowed").unwrap_err();
+    assert_eq!(error.kind, PathErrorKind::NoPayload("windowed".to_string()));
+}
+
+#[test]
+fn wildcards_query_every_match() {
+    let data = r"
+server {
+    port 8080
+    users [
+        {
+            name alice
+            roles [admin dev]
+        }
+        {
+            name bob
+            roles [dev]
+        }
+    ]
+}
+mode :windowed
+";
+    let root = Parser::new(data).parse();
+    let names: Vec<_> = root
+        .query("server.users[*].name")
+        .unwrap()
+        .into_iter()
+        .filter_map(Value::as_str)
+        .collect();
+    assert_eq!(names, ["alice", "bob"]);
+
+    let roles = root.query("server.users.*.roles.*").unwrap();
+    assert_eq!(roles.len(), 3);
+    assert_eq!(root.query("*").unwrap().len(), 2);
+
+    let error = root.get_path("server.users[*].name").unwrap_err();
+    assert_eq!(error.kind, PathErrorKind::Wildcard);
+    assert_eq!(error.at, "server.users[*]");
+}
+
+#[test]
+fn errors_name_the_failing_segment() {
+    let data = r"
+server {
+    port 8080
+    users [{ name alice } { name bob }]
+}
+";
+    let root = Parser::new(data).parse();
+
+    let error = root.get_path("server.users[5].name").unwrap_err();
+    assert_eq!(error.at, "server.users[5]");
+    assert_eq!(error.segment, 2);
+    assert_eq!(
+        error.to_string(),
+        "index 5 is out of range for 2 items at `server.users[5]`"
+    );
+
+    let error = root.get_path("server.host").unwrap_err();
+    assert_eq!(error.kind, PathErrorKind::MissingKey("host".to_string()));
+    assert_eq!(error.to_string(), "no field `host` at `server.host`");
+
+    let error = root.get_path("server.port.number").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected a struct, found an integer at `server.port.number`"
+    );
+
+    let error = root.get_path("server[0]").unwrap_err();
+    assert_eq!(
+        error.kind,
+        PathErrorKind::WrongKind {
+            expected: "an array or tuple",
+            found: "a struct",
+        }
+    );
+}
+
+#[test]
+fn malformed_paths() {
+    let root = Parser::new("server {\n    port 8080\n}\n").parse();
+    for (path, at, message) in [
+        ("", "", "expected a key"),
+        ("server..port", "server..", "expected a key"),
+        (
+            "server.users[x]",
+            "server.users[x",
+            "expected an index, `*` or a quoted key",
+        ),
+        ("server.users[0", "server.users[0", "expected `]`"),
+        ("server port", "server ", "expected `.`, `[` or `:`"),
+        (
+            r#"server."port"#,
+            r#"server."port"#,
+            "quoted key is missing its closing `\"`",
+        ),
+    ] {
+        let error = root.get_path(path).unwrap_err();
+        assert_eq!(error.at, at, "{path}");
+        assert_eq!(error.kind, PathErrorKind::Syntax(message), "{path}");
+    }
+}
+
+#[test]
+fn get_path_mut_changes_values_in_place() {
+    let data = r"
+server {
+    users [{ name alice }]
+}
+screen :fullscreen(1024 768)
+";
+    let mut root = Parser::new(data).parse();
+    *root.get_path_mut("server.users[0].name").unwrap() = Value::Str("carol".to_string());
+    *root.get_path_mut("screen:fullscreen.1").unwrap() = Value::Int(1080);
+    assert_eq!(
+        root.get_path("server.users.0.name").unwrap().as_str(),
+        Some("carol")
+    );
+    assert_eq!(
+        root.get_path("screen:fullscreen[1]").unwrap().as_int(),
+        Some(1080)
+    );
+    assert!(root.get_path_mut("server.users[*]").is_err());
+}
+
+#[test]
+fn values_take_paths_too() {
+    let data = r"
+server {
+    users [
+        { name alice }
+        {
+            name bob
+            roles [dev]
+        }
+    ]
+}
+";
+    let root = Parser::new(data).parse();
+    let users = root.get_path("server.users").unwrap();
+    assert_eq!(
+        users.get_path("[1].roles[0]").unwrap().as_str(),
+        Some("dev")
+    );
+    assert_eq!(users.query("*.name").unwrap().len(), 2);
+}
@@ -0,0 +1,458 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+#![cfg(feature = "serde")]
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use yini::de::{from_struct, from_value};
+use yini::{Parser, Value};
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Server {
+    host: String,
+    port: u16,
+    ssl: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+enum Mode {
+    Fullscreen,
+    Windowed(u32, u32),
+    Scaled(f32),
+    Custom { width: u32, height: u32 },
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Point(i32, i32);
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Config {
+    name: String,
+    description: String,
+    server: Server,
+    timeout: f64,
+    retries: Option<u8>,
+    tags: Vec<String>,
+    origin: Point,
+    pair: (String, i64),
+    modes: Vec<Mode>,
+    limits: HashMap<String, u32>,
+}
+
+const DATA: &str = r#"
+# Game settings
+name "Main"
+description this is a long description
+server {
+    host localhost
+    port 8080
+    ssl true
+}
+timeout 30
+retries 3
+tags [fast "very safe"]
+origin (10 -20)
+pair ("answer" 42)
+modes [
+    :Fullscreen
+    :Windowed(1024 768)
+    :Scaled(1.5)
+    :Custom{
+        width 800
+        height 600
+    }
+]
+limits {
+    players 16
+    bots 4
+}
+"#;
+
+fn expected() -> Config {
+    Config {
+        name: "Main".to_string(),
+        description: "this is a long description".to_string(),
+        server: Server {
+            host: "localhost".to_string(),
+            port: 8080,
+            ssl: true,
+        },
+        timeout: 30.0,
+        retries: Some(3),
+        tags: vec!["fast".to_string(), "very safe".to_string()],
+        origin: Point(10, -20),
+        pair: ("answer".to_string(), 42),
+        modes: vec![
+            Mode::Fullscreen,
+            Mode::Windowed(1024, 768),
+            Mode::Scaled(1.5),
+            Mode::Custom {
+                width: 800,
+                height: 600,
+            },
+        ],
+        limits: HashMap::from([("players".to_string(), 16), ("bots".to_string(), 4)]),
+    }
+}
+
+#[test]
+fn deserialize_from_text() {
+    let config: Config = yini::from_str(DATA).unwrap();
+    assert_eq!(config, expected());
+}
+
+#[test]
+fn deserialize_from_parsed_struct() {
+    let mut parser = Parser::new(DATA);
+    let root = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+
+    let config: Config = from_struct(&root).unwrap();
+    assert_eq!(config, expected());
+
+    let server: Server = from_value(root.get("server").unwrap()).unwrap();
+    assert_eq!(server, expected().server);
+}
+
+#[test]
+fn unit_variant_from_plain_word() {
+    #[derive(Deserialize)]
+    struct Window {
+        mode: Mode,
+    }
+    let window: Window = yini::from_str("mode Fullscreen\n").unwrap();
+    assert_eq!(window.mode, Mode::Fullscreen);
+}
+
+#[test]
+fn type_error_has_location() {
+    let data = "host localhost\nport \"eighty\"\nssl true\n";
+    let err = yini::from_str::<Server>(data).unwrap_err();
+    assert_eq!(err.line(), Some(2));
+    assert_eq!(err.column(), Some(6));
+}
+
+#[test]
+fn out_of_range_error_has_location() {
+    let data = "host localhost\nssl true\nport 70000\n";
+    let err = yini::from_str::<Server>(data).unwrap_err();
+    assert_eq!(err.line(), Some(3));
+    assert_eq!(err.column(), Some(6));
+}
+
+#[test]
+fn nested_error_points_at_item() {
+    let data = "origin (10\n    twenty)\n";
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Origin {
+        origin: Point,
+    }
+    let err = yini::from_str::<Origin>(data).unwrap_err();
+    assert_eq!(err.line(), Some(2));
+    assert_eq!(err.column(), Some(5));
+}
+
+#[test]
+fn missing_field_is_reported() {
+    let err = yini::from_str::<Server>("host localhost\n").unwrap_err();
+    assert!(err.message().contains("port"), "{err}");
+}
+
+#[test]
+fn deserialize_comma_separated_items() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Lists {
+        tags: Vec<String>,
+        origin: Point,
+        modes: Vec<Mode>,
+    }
+    let data = "tags [fast, \"very safe\",]\norigin (10, -20)\nmodes [:Windowed(1024, 768), :Scaled(1.5,)]\n";
+    let lists: Lists = yini::from_str(data).unwrap();
+    assert_eq!(
+        lists,
+        Lists {
+            tags: vec!["fast".to_string(), "very safe".to_string()],
+            origin: Point(10, -20),
+            modes: vec![Mode::Windowed(1024, 768), Mode::Scaled(1.5)],
+        }
+    );
+
+    let err = yini::from_str::<Lists>("tags [a,, b]\n").unwrap_err();
+    assert_eq!((err.line(), err.column()), (Some(1), Some(9)));
+}
+
+#[test]
+fn wide_integers_round_trip() {
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Ids {
+        hash: u64,
+        total: i128,
+        small: u64,
+    }
+    let ids = Ids {
+        hash: u64::MAX,
+        total: i128::MIN,
+        small: 7,
+    };
+    let text = yini::to_string(&ids).unwrap();
+    assert_eq!(
+        text,
+        "hash 18446744073709551615\ntotal -170141183460469231731687303715884105728\nsmall 7\n"
+    );
+    assert_eq!(yini::from_str::<Ids>(&text).unwrap(), ids);
+    assert_eq!(
+        from_struct::<Ids>(&Parser::new(&text).parse()).unwrap(),
+        ids
+    );
+
+    let err =
+        yini::from_str::<Server>("host a\nssl true\nport 0xFFFF_FFFF_FFFF_FFFF\n").unwrap_err();
+    assert_eq!(err.line(), Some(3));
+}
+
+#[test]
+fn durations_and_dates_round_trip() {
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Retry(#[serde(with = "yini::ser::duration")] std::time::Duration);
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Timeouts {
+        #[serde(with = "yini::ser::duration")]
+        connect: std::time::Duration,
+        retries: Vec<Retry>,
+        since: String,
+    }
+    let data = "connect 1.5s\nretries [100ms 1m]\nsince 2024-05-01T12:00:00Z\n";
+    let timeouts: Timeouts = yini::from_str(data).unwrap();
+    assert_eq!(
+        timeouts,
+        Timeouts {
+            connect: std::time::Duration::from_millis(1500),
+            retries: vec![
+                Retry(std::time::Duration::from_millis(100)),
+                Retry(std::time::Duration::from_secs(60))
+            ],
+            since: "2024-05-01T12:00:00Z".to_string(),
+        }
+    );
+    assert_eq!(
+        from_struct::<Timeouts>(&Parser::new(data).parse()).unwrap(),
+        timeouts
+    );
+    assert_eq!(
+        yini::to_string(&timeouts).unwrap(),
+        "connect 1500ms\nretries [100ms 1m]\nsince \"2024-05-01T12:00:00Z\"\n"
+    );
+}
+
+#[test]
+fn unmarked_durations_stay_structs() {
+    #[derive(Serialize)]
+    struct Duration {
+        secs: u64,
+        nanos: u32,
+    }
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Timeouts {
+        connect: std::time::Duration,
+    }
+    #[derive(Serialize)]
+    struct Both {
+        std: std::time::Duration,
+        own: Duration,
+    }
+    let both = Both {
+        std: std::time::Duration::from_secs(1),
+        own: Duration { secs: 1, nanos: 0 },
+    };
+    let value = yini::ser::to_value(&both).unwrap();
+    let root = value.as_struct().unwrap();
+    assert!(root.get("std").unwrap().as_struct().is_some());
+    assert!(root.get("own").unwrap().as_struct().is_some());
+
+    let timeouts = Timeouts {
+        connect: std::time::Duration::from_millis(1500),
+    };
+    let text = yini::to_string(&timeouts).unwrap();
+    assert_eq!(yini::from_str::<Timeouts>(&text).unwrap(), timeouts);
+}
+
+#[test]
+fn parse_error_is_reported() {
+    let err = yini::from_str::<Server>("host\nport 1\n").unwrap_err();
+    assert_eq!(err.line(), Some(1));
+}
+
+#[test]
+fn serialize_round_trip() {
+    let text = yini::to_string(&expected()).unwrap();
+    let config: Config = yini::from_str(&text).unwrap();
+    assert_eq!(config, expected(), "{text}");
+}
+
+#[test]
+fn serialize_enums_as_variants() {
+    #[derive(Serialize)]
+    struct Window {
+        modes: Vec<Mode>,
+        origin: Point,
+        nothing: (),
+    }
+    let window = Window {
+        modes: vec![
+            Mode::Fullscreen,
+            Mode::Windowed(1024, 768),
+            Mode::Scaled(0.5),
+            Mode::Custom {
+                width: 800,
+                height: 600,
+            },
+        ],
+        origin: Point(1, -2),
+        nothing: (),
+    };
+    assert_eq!(
+        yini::to_string(&window).unwrap(),
+        "modes [\n    :Fullscreen\n    :Windowed(1024 768)\n    :Scaled(0.5)\n    :Custom{\n        width 800\n        height 600\n    }\n]\norigin (1 -2)\nnothing ()\n"
+    );
+}
+
+#[test]
+fn serialize_skips_none_fields() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Settings {
+        name: String,
+        limit: Option<u32>,
+    }
+    let settings = Settings {
+        name: "with space".to_string(),
+        limit: None,
+    };
+    let text = yini::to_string(&settings).unwrap();
+    assert_eq!(text, "name \"with space\"\n");
+    assert_eq!(yini::from_str::<Settings>(&text).unwrap(), settings);
+}
+
+#[test]
+fn null_maps_to_none() {
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Overrides {
+        proxy: Option<String>,
+        limit: Option<u32>,
+        name: Option<String>,
+        retries: Vec<Option<u8>>,
+        pair: (Option<i32>, i32),
+    }
+    let data = "proxy null\nlimit 5\nname nullable\nretries [1 null 3]\npair (null, 2)\n";
+    let expected = Overrides {
+        proxy: None,
+        limit: Some(5),
+        name: Some("nullable".to_string()),
+        retries: vec![Some(1), None, Some(3)],
+        pair: (None, 2),
+    };
+    assert_eq!(yini::from_str::<Overrides>(data).unwrap(), expected);
+    assert_eq!(
+        from_struct::<Overrides>(&Parser::new(data).parse()).unwrap(),
+        expected
+    );
+    assert_eq!(
+        yini::to_string(&expected).unwrap(),
+        "limit 5\nname nullable\nretries [1 null 3]\npair (\n    null\n    2\n)\n"
+    );
+}
+
+#[test]
+fn some_none_is_written_as_null() {
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Patch {
+        proxy: Option<Option<String>>,
+        limit: Option<Option<u32>>,
+        name: Option<Option<String>>,
+    }
+    let patch = Patch {
+        proxy: Some(None),
+        limit: None,
+        name: Some(Some("main".to_string())),
+    };
+    let text = yini::to_string(&patch).unwrap();
+    assert_eq!(text, "proxy null\nname main\n");
+    assert_eq!(Parser::new(&text).parse().get("proxy"), Some(&Value::Null));
+
+    // There is only one `null`, so `Some(None)` reads back as `None`
+    let back: Patch = yini::from_str(&text).unwrap();
+    assert_eq!(
+        back,
+        Patch {
+            proxy: None,
+            ..patch
+        }
+    );
+}
+
+#[test]
+fn bytes_fill_byte_vectors() {
+    struct Blob<'a>(&'a [u8]);
+    impl Serialize for Blob<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Asset {
+        icon: Vec<u8>,
+        key: Vec<u8>,
+        list: Vec<u8>,
+    }
+    let data = "icon b64\"aGk=\"\nkey hex\"0102\"\nlist [3 4]\n";
+    let expected = Asset {
+        icon: b"hi".to_vec(),
+        key: vec![1, 2],
+        list: vec![3, 4],
+    };
+    assert_eq!(yini::from_str::<Asset>(data).unwrap(), expected);
+    assert_eq!(
+        from_struct::<Asset>(&Parser::new(data).parse()).unwrap(),
+        expected
+    );
+
+    let mut blobs = HashMap::new();
+    blobs.insert("icon", Blob(b"hi"));
+    assert_eq!(yini::to_string(&blobs).unwrap(), "icon b64\"aGk=\"\n");
+}
+
+#[test]
+fn serialize_rejects_non_struct_root() {
+    assert!(yini::to_string(&[1, 2, 3]).is_err());
+    assert!(yini::to_string(&u64::MAX).is_err());
+}
+
+#[test]
+fn integer_and_bool_keys_round_trip() {
+    let names = BTreeMap::from([(1u32, "one".to_string()), (20, "twenty".to_string())]);
+    let text = yini::to_string(&names).unwrap();
+    assert_eq!(text, "1 one\n20 twenty\n");
+    assert_eq!(
+        yini::from_str::<BTreeMap<u32, String>>(&text).unwrap(),
+        names
+    );
+    assert_eq!(
+        from_struct::<BTreeMap<u32, String>>(&Parser::new(&text).parse()).unwrap(),
+        names
+    );
+
+    let flags = BTreeMap::from([(false, 'n'), (true, 'y')]);
+    let text = yini::to_string(&flags).unwrap();
+    assert_eq!(
+        yini::from_str::<BTreeMap<bool, char>>(&text).unwrap(),
+        flags
+    );
+
+    let err = yini::from_str::<BTreeMap<u32, String>>("x one\n").unwrap_err();
+    assert_eq!(err.line(), Some(1));
+}
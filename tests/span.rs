@@ -0,0 +1,99 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use yini::Parser;
+use yini::span::{Span, SpanChildren};
+
+const DATA: &str = r#"# settings
+name "Main"
+server {
+    port: 12
+    hosts [alpha "beta"]
+}
+pair (1 two words)
+mode :windowed(800 600)
+motd welcome to the server
+"#;
+
+fn text(span: &Span) -> &'static str {
+    &DATA[span.range()]
+}
+
+#[test]
+fn spans_for_keys_and_values() {
+    let mut parser = Parser::new(DATA);
+    let (root, spans) = parser.parse_spanned();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    assert_eq!(root.len(), spans.len());
+
+    let name = spans.get("name").unwrap();
+    assert_eq!(text(&name.key), "name");
+    assert_eq!(text(&name.value.span), "\"Main\"");
+    assert_eq!(name.key.start.line, 2);
+    assert_eq!(name.key.start.column, 1);
+    assert_eq!(name.value.span.start.column, 6);
+
+    let server = spans.get("server").unwrap();
+    assert!(text(&server.value.span).starts_with('{'));
+    assert!(text(&server.value.span).ends_with('}'));
+
+    let port = server.value.field("port").unwrap();
+    assert_eq!(text(&port.key), "port");
+    assert_eq!(text(&port.value.span), "12");
+    assert_eq!(port.value.span.start.line, 4);
+    assert_eq!(port.value.span.start.column, 11);
+    assert_eq!(port.value.span.end.column, 13);
+
+    let hosts = &server.value.field("hosts").unwrap().value;
+    assert_eq!(text(&hosts.item(0).unwrap().span), "alpha");
+    assert_eq!(text(&hosts.item(1).unwrap().span), "\"beta\"");
+    assert!(hosts.item(2).is_none());
+}
+
+#[test]
+fn spans_for_tuples_and_variants() {
+    let mut parser = Parser::new(DATA);
+    let (_, spans) = parser.parse_spanned();
+
+    let pair = &spans.get("pair").unwrap().value;
+    assert_eq!(text(&pair.span), "(1 two words)");
+    assert_eq!(text(&pair.item(0).unwrap().span), "1");
+    assert_eq!(text(&pair.item(1).unwrap().span), "two words");
+
+    let mode = &spans.get("mode").unwrap().value;
+    assert_eq!(text(&mode.span), ":windowed(800 600)");
+    let SpanChildren::Variant { name, payload } = &mode.children else {
+        panic!("mode should have variant spans");
+    };
+    assert_eq!(text(name), "windowed");
+    let payload = payload.as_deref().unwrap();
+    assert_eq!(text(&payload.span), "(800 600)");
+    assert_eq!(text(&payload.item(1).unwrap().span), "600");
+}
+
+#[test]
+fn rest_of_line_string_spans_whole_text() {
+    let mut parser = Parser::new(DATA);
+    let (root, spans) = parser.parse_spanned();
+    let motd = &spans.get("motd").unwrap().value;
+    assert_eq!(text(&motd.span), "welcome to the server");
+    assert_eq!(
+        root.get("motd").and_then(|v| v.as_str()),
+        Some(text(&motd.span))
+    );
+    assert!(matches!(motd.children, SpanChildren::None));
+}
+
+#[test]
+fn spans_skip_fields_with_errors() {
+    let data = "a 1\nb\nc 3\na 4\n";
+    let mut parser = Parser::new(data);
+    let (root, spans) = parser.parse_spanned();
+    assert!(!parser.errors().is_empty());
+    assert_eq!(root.len(), 2);
+    assert_eq!(spans.len(), 2);
+    assert_eq!(&data[spans.get("a").unwrap().value.span.range()], "1");
+    assert_eq!(&data[spans.get("c").unwrap().value.span.range()], "3");
+}
@@ -11,7 +11,7 @@ fn parse_sample() {
             # comment line
             key1: -42
             "key2": 100
-            key3: 3.14
+            key3: 3.25
             key4: -0.5
             key5: "string"
         "#;
@@ -25,7 +25,7 @@ fn parse_sample() {
     );
     assert_eq!(map.get("key1").and_then(Value::as_int), Some(-42));
     assert_eq!(map.get("key2").and_then(Value::as_int), Some(100));
-    assert_eq!(map.get("key3").and_then(Value::as_num), Some(3.14));
+    assert_eq!(map.get("key3").and_then(Value::as_num), Some(3.25));
     assert_eq!(map.get("key4").and_then(Value::as_num), Some(-0.5));
     assert_eq!(map.get("key5").and_then(Value::as_str), Some("string"));
 }
@@ -109,7 +109,7 @@ fn comments() {
 fn flat_array() {
     let data = r#"
             numbers: [1 2 3 4 5]
-            mixed: ["hello"  42 true 3.14]
+            mixed: ["hello"  42 true 3.25]
             spaced: ["This Is" "A list" 23]
         "#;
     let mut parser = Parser::new(data);
@@ -133,7 +133,7 @@ fn flat_array() {
         assert_eq!(mixed[0].as_str(), Some("hello"));
         assert_eq!(mixed[1].as_int(), Some(42));
         assert_eq!(mixed[2].as_bool(), Some(true));
-        assert_eq!(mixed[3].as_num(), Some(3.14));
+        assert_eq!(mixed[3].as_num(), Some(3.25));
     } else {
         panic!("mixed not parsed as array");
     }
@@ -905,3 +905,815 @@ fn struct_keys_with_optional_colons() {
         panic!("config not parsed as struct");
     }
 }
+
+#[test]
+fn single_line_struct() {
+    let data = r"
+            origin { x 1 }
+            nested { inner { y 2 } }
+        ";
+
+    let mut parser = Parser::new(data);
+    let map = parser.parse();
+    assert!(
+        parser.errors().is_empty(),
+        "Parse errors: {:?}",
+        parser.errors()
+    );
+
+    let origin = map.get("origin").and_then(Value::as_struct).unwrap();
+    assert_eq!(origin.get("x").and_then(Value::as_int), Some(1));
+    let inner = map
+        .get("nested")
+        .and_then(Value::as_struct)
+        .and_then(|nested| nested.get("inner"))
+        .and_then(Value::as_struct)
+        .unwrap();
+    assert_eq!(inner.get("y").and_then(Value::as_int), Some(2));
+
+    // A stray closing brace at the top level is still an error
+    let mut parser = Parser::new("a 1 }\n");
+    let _ = parser.parse();
+    assert!(
+        parser
+            .errors()
+            .iter()
+            .any(|e| matches!(e.kind, ErrorKind::UnexpectedCharacter('}')))
+    );
+}
+
+#[test]
+fn errors_display_readable_messages() {
+    let mut parser = Parser::new("port 80 }\nhost\n");
+    let _ = parser.parse();
+    let messages: Vec<String> = parser.errors().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "expected a key, found '}' at line 1, column 9",
+            "expected a value on the same line as the key at line 2, column 5",
+        ]
+    );
+
+    let mut parser = Parser::new("pair (1 2) x\n");
+    let _ = parser.parse();
+    assert_eq!(
+        parser.errors()[0].kind.to_string(),
+        "expected a line break after the value, found 'x'"
+    );
+}
+
+#[test]
+fn errors_combine_into_one_error() {
+    let mut parser = Parser::new("a\nb\n");
+    let _ = parser.parse();
+    let errors = yini::Errors::from(parser.errors().to_vec());
+    assert_eq!(
+        errors.to_string(),
+        "document had 2 errors\n  expected a value on the same line as the key at line 1, column 2\n  expected a value on the same line as the key at line 2, column 2"
+    );
+    assert_eq!(errors.as_slice().len(), 2);
+
+    let boxed: Box<dyn std::error::Error> = Box::new(errors);
+    assert!(boxed.source().is_none());
+
+    assert_eq!(
+        yini::Errors::from(Vec::new()).to_string(),
+        "document had no errors"
+    );
+}
+
+#[test]
+fn parse_str_fails_on_any_error() {
+    let root = yini::parse_str("name \"Main\"\nport 8080\n").unwrap();
+    assert_eq!(root.get("port").and_then(Value::as_int), Some(8080));
+
+    let errors = yini::parse_str("name \"Main\"\nport\nhost\n").unwrap_err();
+    assert_eq!(errors.as_slice().len(), 2);
+    assert!(matches!(
+        errors.as_slice()[0].kind,
+        ErrorKind::ExpectedValueOnSameLine
+    ));
+
+    assert!(Parser::new("a 1\n").finish().is_ok());
+    assert!(Parser::new("a {\n").finish().is_err());
+}
+
+#[test]
+fn parse_file_reads_and_parses() {
+    let path = std::env::temp_dir().join(format!("yini-parse-file-{}.yini", std::process::id()));
+    std::fs::write(&path, "threads 4\n").unwrap();
+    let root = yini::parse_file(&path).unwrap();
+    assert_eq!(root.get("threads").and_then(Value::as_int), Some(4));
+
+    std::fs::write(&path, "threads\n").unwrap();
+    assert!(matches!(
+        yini::parse_file(&path),
+        Err(yini::FileError::Parse(_))
+    ));
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        yini::parse_file(&path),
+        Err(yini::FileError::Io(_))
+    ));
+}
+
+fn parse_with(data: &str, duplicate_keys: yini::DuplicateKeys) -> (yini::Struct, Parser<'_>) {
+    let mut parser = Parser::with_options(
+        data,
+        yini::ParserOptions {
+            duplicate_keys,
+            ..Default::default()
+        },
+    );
+    let root = parser.parse();
+    (root, parser)
+}
+
+const DUPLICATES: &str = "port 1\nhost a\nserver {\n    port 2\n    port 3\n}\nport 4\n";
+
+#[test]
+fn duplicate_keys_are_errors_by_default() {
+    let mut parser = Parser::new(DUPLICATES);
+    let root = parser.parse();
+    assert_eq!(root.get("port").and_then(Value::as_int), Some(1));
+    let errors = parser.errors();
+    assert_eq!(errors.len(), 2);
+    assert_eq!((errors[0].line, errors[0].column), (5, 5));
+    assert!(matches!(
+        &errors[0].kind,
+        ErrorKind::DuplicateKey { key, first_line: 4, first_column: 5 } if key == "port"
+    ));
+    assert_eq!(
+        errors[1].to_string(),
+        "duplicate key `port` (first defined at 1:1) at line 7, column 1"
+    );
+}
+
+#[test]
+fn duplicate_key_policies() {
+    use yini::DuplicateKeys;
+
+    let (root, parser) = parse_with(DUPLICATES, DuplicateKeys::Warn);
+    assert!(parser.errors().is_empty());
+    assert_eq!(parser.warnings().len(), 2);
+    assert_eq!(root.get("port").and_then(Value::as_int), Some(1));
+
+    let (root, parser) = parse_with(DUPLICATES, DuplicateKeys::FirstWins);
+    assert!(parser.errors().is_empty() && parser.warnings().is_empty());
+    assert_eq!(root.get("port").and_then(Value::as_int), Some(1));
+
+    let (root, parser) = parse_with(DUPLICATES, DuplicateKeys::LastWins);
+    assert!(parser.errors().is_empty());
+    assert_eq!(root.get("port").and_then(Value::as_int), Some(4));
+    assert_eq!(root.keys().next().map(String::as_str), Some("port"));
+    let server = root.get("server").and_then(Value::as_struct).unwrap();
+    assert_eq!(server.get("port").and_then(Value::as_int), Some(3));
+
+    let (root, parser) = parse_with(
+        "tags [a]\ntags [b]\ntags c\nsingle 1\n",
+        DuplicateKeys::Collect,
+    );
+    assert!(parser.errors().is_empty());
+    assert_eq!(
+        root.get("tags"),
+        Some(&Value::Array(vec![
+            Value::Array(vec![Value::Str("a".to_string())]),
+            Value::Array(vec![Value::Str("b".to_string())]),
+            Value::Str("c".to_string()),
+        ]))
+    );
+    assert_eq!(root.get("single").and_then(Value::as_int), Some(1));
+}
+
+fn parse_strict(data: &str) -> Vec<yini::ParseError> {
+    let mut parser = Parser::with_options(
+        data,
+        yini::ParserOptions {
+            strict: true,
+            ..Default::default()
+        },
+    );
+    let _ = parser.parse();
+    parser.errors().to_vec()
+}
+
+#[test]
+fn strict_mode_requires_quotes_around_multiple_words() {
+    let data = "port 80 81
+title \"Main menu\"
+pair (1 two words)
+name main
+";
+    let mut parser = Parser::new(data);
+    assert_eq!(
+        parser.parse().get("port").and_then(Value::as_str),
+        Some("80 81")
+    );
+    assert!(parser.errors().is_empty());
+
+    let errors = parse_strict(data);
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(&errors[0].kind, ErrorKind::UnquotedString(text) if text == "80 81"));
+    assert_eq!((errors[0].line, errors[0].column), (1, 6));
+    assert!(matches!(&errors[1].kind, ErrorKind::UnquotedString(text) if text == "two words"));
+    assert_eq!((errors[1].line, errors[1].column), (3, 9));
+    assert_eq!(
+        errors[0].to_string(),
+        "`80 81` must be quoted to be one string at line 1, column 6"
+    );
+}
+
+#[test]
+fn strict_mode_rejects_invalid_bare_words() {
+    let errors = parse_strict("a hello!\nb [ok wh@t]\nc (1 x!)\nd path/to/file.txt\ne true\n");
+    let words: Vec<_> = errors
+        .iter()
+        .map(|error| match &error.kind {
+            ErrorKind::InvalidBareWord(word) => word.as_str(),
+            other => panic!("unexpected error {other:?}"),
+        })
+        .collect();
+    assert_eq!(words, ["hello!", "wh@t", "x!"]);
+}
+
+#[test]
+fn commas_separate_items() {
+    let data = "coords [1, 2, 3]\ntags [developer, rust,]\npairs [ (k1, \"v1\") (\"k2\", \"v2\") ]\ntriple (a, b c, d)\nmixed [\n    :a(1,2),\n    x y\n]\n";
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    let ints: Vec<_> = root
+        .get("coords")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(Value::as_int)
+        .collect();
+    assert_eq!(ints, [Some(1), Some(2), Some(3)]);
+    let tags: Vec<_> = root
+        .get("tags")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(Value::as_str)
+        .collect();
+    assert_eq!(tags, [Some("developer"), Some("rust")]);
+    let Some(Value::Array(pairs)) = root.get("pairs") else {
+        panic!("pairs should be an array");
+    };
+    assert!(
+        pairs
+            .iter()
+            .all(|pair| matches!(pair, Value::Tuple(items) if items.len() == 2))
+    );
+    let Some(Value::Tuple(triple)) = root.get("triple") else {
+        panic!("triple should be a tuple");
+    };
+    let triple: Vec<_> = triple.iter().map(Value::as_str).collect();
+    assert_eq!(triple, [Some("a"), Some("b c"), Some("d")]);
+    let Some(Value::Array(mixed)) = root.get("mixed") else {
+        panic!("mixed should be an array");
+    };
+    assert!(
+        matches!(&mixed[0], Value::Variant(name, Some(payload)) if name == "a" && matches!(&**payload, Value::Tuple(items) if items.len() == 2))
+    );
+    assert_eq!(mixed.len(), 3);
+}
+
+#[test]
+fn doubled_commas_are_errors() {
+    let mut parser = Parser::new("a [1,, 2]\nb (, x)\nc [1 2]\n");
+    let root = parser.parse();
+    let positions: Vec<_> = parser
+        .errors()
+        .iter()
+        .filter(|error| matches!(error.kind, ErrorKind::UnexpectedComma))
+        .map(|error| (error.line, error.column))
+        .collect();
+    assert_eq!(positions, [(1, 6), (2, 4)]);
+    assert_eq!(parser.errors().len(), 2);
+    assert_eq!(root.get("a").unwrap().as_array().unwrap().len(), 2);
+    assert!(matches!(root.get("b"), Some(Value::Tuple(items)) if items.len() == 1));
+}
+
+#[test]
+fn crlf_lone_cr_and_bom_read_like_lf() {
+    let lf = "# settings\nname \"Main\"\nserver {\n    port 80 # http\n}\ntags [a, b]\ntext \"two\nlines\"\n";
+    let expected = yini::parse_str(lf).unwrap();
+    for text in [
+        lf.replace('\n', "\r\n"),
+        lf.replace('\n', "\r"),
+        format!("\u{feff}{}", lf.replace('\n', "\r\n")),
+    ] {
+        let root = yini::parse_str(&text).unwrap_or_else(|errors| panic!("{text:?}: {errors}"));
+        assert_eq!(Value::Struct(root), Value::Struct(expected.clone()));
+    }
+
+    for text in ["a 1\r\nb\r\nc 3\r\n", "a 1\rb\rc 3\r", "\u{feff}a 1\nb\n"] {
+        let mut parser = Parser::new(text);
+        let _ = parser.parse();
+        let error = &parser.errors()[0];
+        assert!(matches!(error.kind, ErrorKind::ExpectedValueOnSameLine));
+        assert_eq!((error.line, error.column), (2, 2), "{text:?}");
+    }
+}
+
+#[test]
+fn string_escapes() {
+    let data = r#"basic "tab\tnul\0quote\"slash\\"
+hex "\x41\x7e"
+unicode "\u{e9}\u{1F600} é😀"
+continued "one \
+           two"
+"#;
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    let text = |key: &str| root.get(key).and_then(Value::as_str).unwrap().to_string();
+    assert_eq!(text("basic"), "tab\tnul\0quote\"slash\\");
+    assert_eq!(text("hex"), "A~");
+    assert_eq!(text("unicode"), "é😀 é😀");
+    assert_eq!(text("continued"), "one two");
+}
+
+#[test]
+fn invalid_escapes_are_errors() {
+    let data = r#"a "\q\é"
+b "\x80 \x4"
+c "\uD800 \uDE00 \uD83Dx \u{110000} \u{}"
+"#;
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    let escapes: Vec<_> = parser
+        .errors()
+        .iter()
+        .map(|error| match &error.kind {
+            ErrorKind::InvalidEscape(escape) => (escape.as_str(), error.line, error.column),
+            other => panic!("unexpected error {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        escapes,
+        [
+            (r"\q", 1, 4),
+            (r"\é", 1, 6),
+            (r"\x80", 2, 4),
+            (r"\x4", 2, 9),
+            (r"\uD800", 3, 4),
+            (r"\uDE00", 3, 11),
+            (r"\uD83D", 3, 18),
+            (r"\u{110000}", 3, 26),
+            (r"\u{}", 3, 37),
+        ]
+    );
+    assert_eq!(root.get("a").and_then(Value::as_str), Some("qé"));
+}
+
+#[test]
+fn multiline_strings_strip_shared_indentation() {
+    let data = "shader \"\"\"\n    void main() {\n        gl_Position = pos;\\t// \"quoted\"\n    }\n    \"\"\"\nsql \"\"\"SELECT *\n  FROM t\"\"\"\nlines [\n    \"\"\"\n      a\n\n      b \\\n      c\n    \"\"\"\n]\nkept \"\"\"\n      indented\n    \"\"\"\n";
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    assert_eq!(
+        root.get("shader").and_then(Value::as_str),
+        Some("void main() {\n    gl_Position = pos;\t// \"quoted\"\n}")
+    );
+    assert_eq!(
+        root.get("sql").and_then(Value::as_str),
+        Some("SELECT *\n  FROM t")
+    );
+    let lines = root.get("lines").and_then(Value::as_array).unwrap();
+    assert_eq!(lines[0].as_str(), Some("  a\n\n  b c"));
+    assert_eq!(root.get("kept").and_then(Value::as_str), Some("  indented"));
+
+    let crlf = Parser::new("a \"\"\"\r\n  x\r\n  y\r\n  \"\"\"\r\n").parse();
+    assert_eq!(crlf.get("a").and_then(Value::as_str), Some("x\ny"));
+}
+
+#[test]
+fn raw_strings_keep_backslashes() {
+    let data = r###"path r"C:\temp\new"
+regex r#""\d+" \w"#
+pair (r"\n", r##"a "# b"##)
+list [r"x" r#"y"#]
+"###;
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    assert_eq!(
+        root.get("path").and_then(Value::as_str),
+        Some(r"C:\temp\new")
+    );
+    assert_eq!(
+        root.get("regex").and_then(Value::as_str),
+        Some(r#""\d+" \w"#)
+    );
+    let Some(Value::Tuple(pair)) = root.get("pair") else {
+        panic!("pair should be a tuple");
+    };
+    let pair: Vec<_> = pair.iter().map(Value::as_str).collect();
+    assert_eq!(pair, [Some(r"\n"), Some(r##"a "# b"##)]);
+    assert_eq!(root.get("list").and_then(Value::as_array).unwrap().len(), 2);
+
+    let mut parser = Parser::new("a r#\"open\"\nb \"\"\"never closed\n");
+    let _ = parser.parse();
+    let starts: Vec<_> = parser
+        .errors()
+        .iter()
+        .filter(|error| matches!(error.kind, ErrorKind::UnterminatedString))
+        .map(|error| (error.line, error.column))
+        .collect();
+    assert_eq!(starts, [(1, 3)]);
+}
+
+#[test]
+fn extended_numeric_literals() {
+    let data = "hex 0xFF_80_00\nmask 0b1010\nmode 0o755\nneg -0x10\nbig 1_000_000\nplus +5\nexp 1e-3\nupper 2.5E+2\nhalf .5\nneg_half -.5\nlimits [inf -inf +inf]\nratio (nan, 1_0.0_1)\nmin -0x8000_0000_0000_0000\n";
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    let int = |key: &str| root.get(key).and_then(Value::as_int);
+    let num = |key: &str| root.get(key).and_then(Value::as_num);
+    assert_eq!(int("hex"), Some(0xFF_80_00));
+    assert_eq!(int("mask"), Some(10));
+    assert_eq!(int("mode"), Some(0o755));
+    assert_eq!(int("neg"), Some(-16));
+    assert_eq!(int("big"), Some(1_000_000));
+    assert_eq!(int("plus"), Some(5));
+    assert_eq!(int("min"), Some(i64::MIN));
+    assert_eq!(num("exp"), Some(0.001));
+    assert_eq!(num("upper"), Some(250.0));
+    assert_eq!(num("half"), Some(0.5));
+    assert_eq!(num("neg_half"), Some(-0.5));
+    let limits: Vec<_> = root
+        .get("limits")
+        .and_then(Value::as_array)
+        .unwrap()
+        .iter()
+        .map(Value::as_num)
+        .collect();
+    assert_eq!(
+        limits,
+        [
+            Some(f64::INFINITY),
+            Some(f64::NEG_INFINITY),
+            Some(f64::INFINITY)
+        ]
+    );
+    let Some(Value::Tuple(ratio)) = root.get("ratio") else {
+        panic!("ratio should be a tuple");
+    };
+    assert!(ratio[0].as_num().unwrap().is_nan());
+    assert_eq!(ratio[1].as_num(), Some(10.01));
+}
+
+#[test]
+fn text_starting_with_digits_stays_text() {
+    let data = "name 4th street\nversion 1.2.3\nip 192.168.0.1\nres 1920x1080\nnote 12abc # comment\nlist [1.2.3 12abc]\npair (1920x1080, 5kg)\n";
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    for (key, text) in [
+        ("name", "4th street"),
+        ("version", "1.2.3"),
+        ("ip", "192.168.0.1"),
+        ("res", "1920x1080"),
+        ("note", "12abc"),
+    ] {
+        assert_eq!(root.get(key).and_then(Value::as_str), Some(text), "{key}");
+    }
+    let text = |items: &[&str]| items.iter().map(|item| Value::from(*item)).collect();
+    assert_eq!(
+        root.get("list"),
+        Some(&Value::Array(text(&["1.2.3", "12abc"])))
+    );
+    assert_eq!(
+        root.get("pair"),
+        Some(&Value::Tuple(text(&["1920x1080", "5kg"])))
+    );
+}
+
+#[test]
+fn malformed_numbers_are_reported_whole() {
+    let data = "a 0xFG\nb 1__\nc [0xZ]\nd 0x1_0000_0000_0000_0000_0000_0000_0000_0000\ne (0o8, 1)\nf 0b102\ng [12_]\nh -info\n";
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    let errors: Vec<_> = parser
+        .errors()
+        .iter()
+        .map(|error| match &error.kind {
+            ErrorKind::InvalidIntegerFormat(text) => (error.line, "int", text.as_str()),
+            ErrorKind::InvalidFloatFormat(text) => (error.line, "float", text.as_str()),
+            other => panic!("unexpected error {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (1, "int", "0xFG"),
+            (2, "int", "1__"),
+            (3, "int", "0xZ"),
+            (4, "int", "0x1_0000_0000_0000_0000_0000_0000_0000_0000"),
+            (5, "int", "0o8"),
+            (6, "int", "0b102"),
+            (7, "int", "12_"),
+        ]
+    );
+    assert_eq!(root.get("h").and_then(Value::as_str), Some("-info"));
+}
+
+#[test]
+fn overflowing_floats_are_reported() {
+    let mut parser = Parser::new(
+        "a 1e400
+b [-1.5e999]
+c 1e-400
+",
+    );
+    let root = parser.parse();
+    let errors: Vec<_> = parser
+        .errors()
+        .iter()
+        .map(|error| (error.line, &error.kind))
+        .collect();
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [
+                (1, ErrorKind::InvalidFloatFormat(a)),
+                (2, ErrorKind::InvalidFloatFormat(b)),
+            ] if a == "1e400" && b == "-1.5e999"
+        ),
+        "{errors:?}"
+    );
+    // Underflowing to zero loses nothing that can be written
+    assert_eq!(root.get("c").and_then(Value::as_num), Some(0.0));
+}
+
+#[test]
+fn wide_integers_beyond_i64() {
+    let data = "hash 0xFFFF_FFFF_FFFF_FFFF\nseed 18446744073709551615\nlow -170141183460469231731687303715884105728\nsmall 0xFF\n";
+    let root = yini::parse_str(data).unwrap();
+    let hash = root.get("hash").unwrap();
+    assert_eq!(*hash, Value::WideInt(i128::from(u64::MAX)));
+    assert_eq!(hash.as_u64(), Some(u64::MAX));
+    assert_eq!(hash.as_int(), None);
+    assert_eq!(root.get("seed"), Some(hash));
+    assert_eq!(root.get("low").and_then(Value::as_i128), Some(i128::MIN));
+    assert_eq!(root.get("low").and_then(Value::as_u64), None);
+
+    let small = root.get("small").unwrap();
+    assert_eq!(*small, Value::Int(255));
+    assert_eq!(small.as_i128(), Some(255));
+    assert_eq!(small.as_integer::<u8>(), Some(255));
+    assert_eq!(small.as_integer::<i8>(), None);
+    assert_eq!(Value::integer(-1), Value::Int(-1));
+}
+
+#[test]
+fn dates_and_durations() {
+    use std::time::Duration;
+    use yini::datetime::{Date, DateTime, Offset, Time};
+
+    let data = "released 2024-02-29\nbackup 2024-05-01T03:00:00\ndeadline 2024-05-01t17:30:00.25-02:30\nutc 1999-12-31T23:59:60Z\ntimeouts [250ms 5s 2h 1.5s 1h30m 1_000us 3d 0s]\n";
+    let root = yini::parse_str(data).unwrap();
+    let date = |key: &str| *root.get(key).and_then(Value::as_datetime).unwrap();
+    assert_eq!(
+        date("released"),
+        DateTime {
+            date: Date {
+                year: 2024,
+                month: 2,
+                day: 29
+            },
+            time: None,
+            offset: None,
+        }
+    );
+    assert_eq!(date("backup").to_string(), "2024-05-01T03:00:00");
+    let deadline = date("deadline");
+    assert_eq!(
+        deadline.time,
+        Some(Time {
+            hour: 17,
+            minute: 30,
+            second: 0,
+            nanosecond: 250_000_000
+        })
+    );
+    assert_eq!(deadline.offset, Some(Offset::Minutes(-150)));
+    assert_eq!(deadline.to_string(), "2024-05-01T17:30:00.25-02:30");
+    assert_eq!(date("utc").to_string(), "1999-12-31T23:59:60Z");
+
+    let timeouts: Vec<_> = root
+        .get("timeouts")
+        .and_then(Value::as_array)
+        .unwrap()
+        .iter()
+        .map(|value| value.as_duration().unwrap())
+        .collect();
+    assert_eq!(
+        timeouts,
+        [
+            Duration::from_millis(250),
+            Duration::from_secs(5),
+            Duration::from_secs(7200),
+            Duration::from_millis(1500),
+            Duration::from_secs(5400),
+            Duration::from_millis(1),
+            Duration::from_secs(3 * 86_400),
+            Duration::ZERO,
+        ]
+    );
+}
+
+#[test]
+fn text_starting_like_a_date_stays_text() {
+    let mut parser = Parser::new("range 1000-2000\nspan 1999-2000 season\n");
+    let root = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    assert_eq!(root.get("range").and_then(Value::as_str), Some("1000-2000"));
+    assert_eq!(
+        root.get("span").and_then(Value::as_str),
+        Some("1999-2000 season")
+    );
+}
+
+#[test]
+fn invalid_dates_and_durations_are_reported() {
+    let data = "a 2023-02-29\nb 2024-13-01\nc 2024-05-01T25:00:00\nd 2024-05-01T12:00\ne -5s\nf 1.0000000001s\ng [5sm]\nh [12abc]\n";
+    let mut parser = Parser::new(data);
+    let root = parser.parse();
+    let errors: Vec<_> = parser
+        .errors()
+        .iter()
+        .map(|error| match &error.kind {
+            ErrorKind::InvalidDateTime(text) => (error.line, "date", text.as_str()),
+            ErrorKind::InvalidDuration(text) => (error.line, "duration", text.as_str()),
+            other => panic!("unexpected error {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (1, "date", "2023-02-29"),
+            (2, "date", "2024-13-01"),
+            (3, "date", "2024-05-01T25:00:00"),
+            (4, "date", "2024-05-01T12:00"),
+            (5, "duration", "-5s"),
+            (6, "duration", "1.0000000001s"),
+        ]
+    );
+    // Neither is a duration, so both are text
+    assert_eq!(root.get("g"), Some(&Value::Array(vec![Value::from("5sm")])));
+    assert_eq!(
+        root.get("h"),
+        Some(&Value::Array(vec![Value::from("12abc")]))
+    );
+}
+
+#[test]
+fn quantities_with_known_units() {
+    use yini::quantity::{Quantity, UnitRegistry};
+
+    let data = "cache 64MB\nvolume 80%\nfov 90deg\nbuffer 1.5KiB\nempty 0B\nlist [4GB, -3.5%]\nmask 0x1B\n";
+    let root = yini::parse_str(data).unwrap();
+    let quantity = |key: &str| root.get(key).and_then(Value::as_quantity).unwrap().clone();
+    assert_eq!(
+        quantity("cache"),
+        Quantity {
+            amount: 64.0,
+            unit: "MB".to_string()
+        }
+    );
+    assert_eq!(quantity("volume").to_string(), "80%");
+    assert_eq!(quantity("buffer").amount, 1.5);
+    assert_eq!(quantity("empty").unit, "B");
+    assert_eq!(root.get("mask"), Some(&Value::Int(0x1B)));
+    let list = root.get("list").and_then(Value::as_array).unwrap();
+    assert_eq!(list[1].as_quantity().unwrap().to_string(), "-3.5%");
+
+    let units = UnitRegistry::new();
+    assert_eq!(
+        quantity("cache").to_base(&units).unwrap().to_string(),
+        "64000000B"
+    );
+    let fov = quantity("fov").to_base(&units).unwrap();
+    assert!((fov.amount - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+    assert_eq!(fov.unit, "rad");
+    assert_eq!(
+        quantity("buffer").convert("B", &units).unwrap().amount,
+        1536.0
+    );
+    assert_eq!(quantity("cache").convert("deg", &units), None);
+}
+
+#[test]
+fn custom_unit_registry() {
+    use yini::quantity::{UnitError, UnitRegistry};
+
+    let mut units = UnitRegistry::empty();
+    units.insert("px", "px", 1.0).unwrap();
+    units.insert("em", "px", 16.0).unwrap();
+    for name in ["", "m/s", "px2", "µm"] {
+        assert_eq!(
+            units.insert(name, "px", 1.0),
+            Err(UnitError::InvalidName(name.to_string()))
+        );
+    }
+    for name in ["s", "ms", "h", "%d"] {
+        assert_eq!(
+            units.insert(name, "px", 1.0),
+            Err(UnitError::DurationUnit(name.to_string()))
+        );
+    }
+    let mut parser = Parser::with_options(
+        "width 2em\ncache 64MB\nlist [64MB]\npair (64MB, 1px)\n",
+        yini::ParserOptions {
+            units: units.clone(),
+            ..Default::default()
+        },
+    );
+    let root = parser.parse();
+    let width = root.get("width").and_then(Value::as_quantity).unwrap();
+    assert_eq!(width.to_string(), "2em");
+    assert_eq!(width.to_base(&units).unwrap().to_string(), "32px");
+    // An unknown unit reads as text wherever it is
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    assert_eq!(root.get("cache").and_then(Value::as_str), Some("64MB"));
+    assert_eq!(
+        root.get("list"),
+        Some(&Value::Array(vec![Value::Str("64MB".to_string())]))
+    );
+    let pair = root.get("pair").and_then(Value::as_tuple).unwrap();
+    assert_eq!(pair[0], Value::Str("64MB".to_string()));
+    assert_eq!(pair[1].as_quantity().unwrap().to_string(), "1px");
+}
+
+#[test]
+fn null_values() {
+    let data = "proxy null\nlist [1 null \"null\"]\npair (null, 2)\nword nullable\n";
+    let root = yini::parse_str(data).unwrap();
+    assert!(root.get("proxy").unwrap().is_null());
+    assert_eq!(
+        root.get("list"),
+        Some(&Value::Array(vec![
+            Value::Int(1),
+            Value::Null,
+            Value::Str("null".to_string())
+        ]))
+    );
+    assert_eq!(
+        root.get("pair"),
+        Some(&Value::Tuple(vec![Value::Null, Value::Int(2)]))
+    );
+    assert_eq!(root.get("word").and_then(Value::as_str), Some("nullable"));
+}
+
+#[test]
+fn byte_literals() {
+    let data = "icon b64\"aGVsbG8=\"\nunpadded b64\"aGVsbG8\"\nurl b64\"-_8\"\nkey hex\"DEad beef\"\nempty hex\"\"\ntable b64\"\n    AAEC\n    AwQF\n\"\npair (hex\"01\", b64\"Ag==\")\n";
+    let root = yini::parse_str(data).unwrap();
+    let bytes = |key: &str| root.get(key).and_then(Value::as_bytes).unwrap();
+    assert_eq!(bytes("icon"), b"hello");
+    assert_eq!(bytes("unpadded"), b"hello");
+    assert_eq!(bytes("url"), [0xFB, 0xFF]);
+    assert_eq!(bytes("key"), [0xDE, 0xAD, 0xBE, 0xEF]);
+    assert_eq!(bytes("empty"), b"");
+    assert_eq!(bytes("table"), [0, 1, 2, 3, 4, 5]);
+    assert_eq!(
+        root.get("pair"),
+        Some(&Value::Tuple(vec![
+            Value::Bytes(vec![1]),
+            Value::Bytes(vec![2])
+        ]))
+    );
+}
+
+#[test]
+fn invalid_byte_literals_point_at_the_problem() {
+    let data = "a hex\"0g\"\nb b64\"ab!c\"\nc hex\"abc\"\nd b64\"a\"\ne b64\"ab=c\"\nf hex\"00";
+    let mut parser = Parser::new(data);
+    parser.parse();
+    let errors: Vec<_> = parser
+        .errors()
+        .iter()
+        .map(|error| (error.line, error.column, error.kind.to_string()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (1, 8, "'g' is not a hex digit".to_string()),
+            (2, 9, "'!' is not a base64 digit".to_string()),
+            (3, 10, "hex data ends partway through a byte".to_string()),
+            (4, 8, "base64 data ends partway through a byte".to_string()),
+            (5, 10, "'c' is not a base64 digit".to_string()),
+            (6, 3, "string is missing its closing `\"`".to_string()),
+        ]
+    );
+}
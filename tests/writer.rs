@@ -0,0 +1,424 @@
+/*
+ * Copyright (c) Peter Bjorklund. All rights reserved. https://github.com/piot/yini
+ * Licensed under the MIT License. See LICENSE in the project root for license information.
+ */
+
+use yini::{
+    Indent, LineEnding, Parser, Separator, Struct, Value, WriteError, WriteOptions, write_struct,
+    write_struct_with, write_value, write_value_with,
+};
+
+fn parse(data: &str) -> Struct {
+    let mut parser = Parser::new(data);
+    let map = parser.parse();
+    assert!(
+        parser.errors().is_empty(),
+        "Parse errors: {:?}\n{data}",
+        parser.errors()
+    );
+    map
+}
+
+fn assert_round_trip(root: &Struct) {
+    let text = write_struct(root).unwrap();
+    let parsed = parse(&text);
+    assert_eq!(
+        Value::Struct(parsed),
+        Value::Struct(root.clone()),
+        "Round trip failed for:\n{text}"
+    );
+}
+
+#[test]
+fn writes_scalars() {
+    let mut root = Struct::new();
+    root.insert("name".to_string(), Value::Str("Alice".to_string()))
+        .unwrap();
+    root.insert("age".to_string(), Value::Int(-30)).unwrap();
+    root.insert("height".to_string(), Value::Num(1.5)).unwrap();
+    root.insert("whole".to_string(), Value::Num(2.0)).unwrap();
+    root.insert("admin".to_string(), Value::Bool(true)).unwrap();
+
+    assert_eq!(
+        write_struct(&root).unwrap(),
+        "name Alice\nage -30\nheight 1.5\nwhole 2.0\nadmin true\n"
+    );
+    assert_round_trip(&root);
+}
+
+#[test]
+fn quotes_strings_that_would_not_survive_bare() {
+    let mut root = Struct::new();
+    for (key, text) in [
+        ("spaced", "This Is"),
+        ("comment", "a#b"),
+        ("colon", "key: value"),
+        ("brackets", "[1 2]"),
+        ("braces", "{}"),
+        ("keyword", "true"),
+        ("number", "42"),
+        ("empty", ""),
+        ("escapes", "line\n\t\"quoted\" \\"),
+        ("controls", "nul\0 bell\u{7} delete\u{7f} \u{1b}[0m"),
+    ] {
+        root.insert(key.to_string(), Value::Str(text.to_string()))
+            .unwrap();
+    }
+    root.insert("with space".to_string(), Value::Int(1))
+        .unwrap();
+
+    let text = write_struct(&root).unwrap();
+    assert!(text.contains("spaced \"This Is\"\n"), "{text}");
+    assert!(text.contains("\"with space\" 1\n"), "{text}");
+    assert!(
+        text.contains(r#"controls "nul\0 bell\u{7} delete\u{7f} \u{1b}[0m""#),
+        "{text}"
+    );
+    assert_round_trip(&root);
+}
+
+#[test]
+fn round_trips_nested_document() {
+    let data = r#"
+        server {
+            host localhost
+            port 8080
+            "display name" "Main server"
+            limits {
+                rate 2.5
+            }
+        }
+        users [
+            {
+                name "Alice"
+                roles ["admin" user]
+            }
+            {
+                name Bob
+                roles []
+            }
+        ]
+        matrix [[1 2] [3 4] []]
+        pairs [ ("k1" "v1") ("k2" 2) ]
+        flags ("a" true false)
+        empty_struct {}
+        empty_tuple ()
+    "#;
+    assert_round_trip(&parse(data));
+}
+
+#[test]
+fn round_trips_variants() {
+    let data = r#"
+        mode :fullscreen
+        screen :windowed(1024 768)
+        player :player{
+            name "Alice"
+            hp 100
+        }
+        colors :rgb[255 128 0]
+        results [:ok(42) :error("failed") :pending :empty()]
+        nested :outer(:inner(1) [2 3])
+    "#;
+    assert_round_trip(&parse(data));
+}
+
+#[test]
+fn rejects_variants_that_would_not_read_back() {
+    let variant = |name: &str, payload: Option<Value>| {
+        let mut root = Struct::new();
+        root.insert(
+            "v".to_string(),
+            Value::Variant(name.to_string(), payload.map(Box::new)),
+        )
+        .unwrap();
+        root
+    };
+
+    // A scalar payload would read back wrapped in a tuple
+    assert_eq!(
+        write_struct(&variant("x", Some(Value::Int(1)))),
+        Err(WriteError::InvalidVariantPayload("x".to_string()))
+    );
+    for name in ["", "two words", "a(b"] {
+        assert_eq!(
+            write_struct(&variant(name, None)),
+            Err(WriteError::InvalidVariantName(name.to_string())),
+            "{name:?}"
+        );
+    }
+    let nested = Value::Array(vec![Value::Variant("bad name".to_string(), None)]);
+    assert!(write_value(&nested).is_err());
+
+    assert_round_trip(&variant("x", Some(Value::Tuple(vec![Value::Int(1)]))));
+    assert_round_trip(&variant("x", Some(Value::Array(vec![Value::Int(1)]))));
+    assert_round_trip(&variant("x_1", None));
+}
+
+#[test]
+fn rejects_quantities_in_unknown_units() {
+    use yini::quantity::{Quantity, UnitRegistry};
+
+    let quantity = |unit: &str| {
+        Value::Array(vec![Value::Quantity(Quantity {
+            amount: 5.0,
+            unit: unit.to_string(),
+        })])
+    };
+    assert_eq!(write_value(&quantity("MB")).unwrap(), "[5MB]");
+    assert_eq!(
+        write_value(&quantity("kg")),
+        Err(WriteError::InvalidQuantity("5kg".to_string()))
+    );
+
+    let mut units = UnitRegistry::empty();
+    units.insert("kg", "kg", 1.0).unwrap();
+    let options = WriteOptions {
+        units,
+        ..WriteOptions::default()
+    };
+    assert_eq!(
+        write_value_with(&quantity("kg"), &options).unwrap(),
+        "[5kg]"
+    );
+    assert!(write_value_with(&quantity("MB"), &options).is_err());
+}
+
+#[test]
+fn writes_wide_integers() {
+    let mut root = Struct::new();
+    root.insert("hash".to_string(), Value::WideInt(i128::from(u64::MAX)))
+        .unwrap();
+    root.insert("low".to_string(), Value::WideInt(i128::MIN))
+        .unwrap();
+    assert_eq!(
+        write_struct(&root).unwrap(),
+        "hash 18446744073709551615\nlow -170141183460469231731687303715884105728\n"
+    );
+    assert_round_trip(&root);
+}
+
+#[test]
+fn writes_dates_and_durations() {
+    let root = parse("at 2024-05-01T12:30:00.500+02:00\ntimeouts [1.5s 90m 0ms 2d 7ns]\n");
+    assert_eq!(
+        write_struct(&root).unwrap(),
+        "at 2024-05-01T12:30:00.5+02:00\ntimeouts [1500ms 90m 0s 2d 7ns]\n"
+    );
+    assert_round_trip(&root);
+}
+
+#[test]
+fn writes_bytes_as_base64() {
+    let root =
+        parse("a hex\"68656c6c6f\"\nb hex\"ff\"\nc hex\"fffe\"\nd hex\"fffefd\"\ne hex\"\"\n");
+    assert_eq!(
+        write_struct(&root).unwrap(),
+        "a b64\"aGVsbG8=\"\nb b64\"/w==\"\nc b64\"//4=\"\nd b64\"//79\"\ne b64\"\"\n"
+    );
+    assert_round_trip(&root);
+}
+
+#[test]
+fn writes_null() {
+    let root = parse("proxy null\nword \"null\"\npair (null, true, null)\n");
+    assert_eq!(
+        write_struct(&root).unwrap(),
+        "proxy null\nword \"null\"\npair (\n    null\n    true\n    null\n)\n"
+    );
+    assert_round_trip(&root);
+}
+
+#[test]
+fn writes_quantities() {
+    let root = parse("cache 64MB\nvolume 12.5%\nangles (90deg, -0.25turn)\nsize 0B\n");
+    assert_eq!(
+        write_struct(&root).unwrap(),
+        "cache 64MB\nvolume 12.5%\nangles (90deg -0.25turn)\nsize 0B\n"
+    );
+    assert_round_trip(&root);
+}
+
+#[test]
+fn writes_special_floats_and_keyword_strings() {
+    let mut root = Struct::new();
+    root.insert("inf".to_string(), Value::Num(f64::INFINITY))
+        .unwrap();
+    root.insert("neg".to_string(), Value::Num(f64::NEG_INFINITY))
+        .unwrap();
+    root.insert("word".to_string(), Value::Str("nan".to_string()))
+        .unwrap();
+    let text = write_struct(&root).unwrap();
+    assert_eq!(text, "inf inf\nneg -inf\nword \"nan\"\n");
+    assert_round_trip(&root);
+
+    let mut nan = Struct::new();
+    nan.insert("x".to_string(), Value::Num(f64::NAN)).unwrap();
+    assert_eq!(write_struct(&nan).unwrap(), "x nan\n");
+    assert!(
+        parse("x nan\n")
+            .get("x")
+            .unwrap()
+            .as_num()
+            .unwrap()
+            .is_nan()
+    );
+}
+
+#[test]
+fn special_floats_inside_tuples_round_trip() {
+    let mut root = Struct::new();
+    root.insert(
+        "limits".to_string(),
+        Value::Tuple(vec![
+            Value::Num(f64::INFINITY),
+            Value::Int(1),
+            Value::Num(f64::NEG_INFINITY),
+            Value::Num(f64::INFINITY),
+        ]),
+    )
+    .unwrap();
+    let text = write_struct(&root).unwrap();
+    assert_eq!(text, "limits (\n    inf\n    1\n    -inf\n    inf\n)\n");
+    assert_round_trip(&root);
+
+    let mut nan = Struct::new();
+    nan.insert(
+        "ratio".to_string(),
+        Value::Tuple(vec![Value::Num(f64::NAN), Value::Int(1)]),
+    )
+    .unwrap();
+    let Some(Value::Tuple(items)) =
+        parse(&write_struct(&nan).unwrap()).remove(&"ratio".to_string())
+    else {
+        panic!("ratio should stay a tuple");
+    };
+    assert!(items[0].as_num().unwrap().is_nan());
+    assert_eq!(items[1], Value::Int(1));
+}
+
+#[test]
+fn empty_keys_round_trip() {
+    let mut inner = Struct::new();
+    inner.insert(String::new(), Value::Int(2)).unwrap();
+    let mut root = Struct::new();
+    root.insert(String::new(), Value::Int(1)).unwrap();
+    root.insert("inner".to_string(), Value::Struct(inner))
+        .unwrap();
+    let text = write_struct(&root).unwrap();
+    assert_eq!(text, "\"\" 1\ninner {\n    \"\" 2\n}\n");
+    assert_round_trip(&root);
+    assert_eq!(Parser::new(&text).parse_cst().to_string(), text);
+}
+
+#[test]
+fn writes_single_value() {
+    let value = Value::Variant(
+        "windowed".to_string(),
+        Some(Box::new(Value::Tuple(vec![
+            Value::Int(1024),
+            Value::Int(768),
+        ]))),
+    );
+    assert_eq!(write_value(&value).unwrap(), ":windowed(1024 768)");
+    assert_eq!(
+        write_value(&Value::Array(vec![
+            Value::Str("a b".to_string()),
+            Value::Str("c".to_string())
+        ]))
+        .unwrap(),
+        "[\"a b\" c]"
+    );
+}
+
+fn sample() -> Struct {
+    parse(
+        r#"
+        name "Main"
+        origin {
+            x 1
+        }
+        size {
+            w 10
+            h 20
+        }
+        ids [1 2 3]
+        pair (1 2)
+    "#,
+    )
+}
+
+#[test]
+fn indents_with_tabs_and_colons() {
+    let options = WriteOptions {
+        indent: Indent::Tab,
+        key_colon: true,
+        ..WriteOptions::default()
+    };
+    let text = write_struct_with(&sample(), &options).unwrap();
+    assert_eq!(
+        text,
+        "name: Main\norigin: {\n\tx: 1\n}\nsize: {\n\tw: 10\n\th: 20\n}\nids: [1 2 3]\npair: (1 2)\n"
+    );
+    assert_eq!(Value::Struct(parse(&text)), Value::Struct(sample()));
+}
+
+#[test]
+fn writes_comma_separators() {
+    let options = WriteOptions {
+        separator: Separator::Comma,
+        ..WriteOptions::default()
+    };
+    let text = write_struct_with(&sample(), &options).unwrap();
+    assert!(text.contains("ids [1, 2, 3]\n"), "{text}");
+    assert!(text.contains("pair (1, 2)\n"), "{text}");
+}
+
+#[test]
+fn writes_detected_line_endings() {
+    assert_eq!(LineEnding::detect("a 1\r\nb 2\n"), LineEnding::CrLf);
+    assert_eq!(LineEnding::detect("a 1\nb 2\r\n"), LineEnding::Lf);
+    assert_eq!(LineEnding::detect("a 1"), LineEnding::Lf);
+
+    let options = WriteOptions {
+        line_ending: LineEnding::CrLf,
+        ..WriteOptions::default()
+    };
+    let text = write_struct_with(&sample(), &options).unwrap();
+    assert_eq!(text, write_struct(&sample()).unwrap().replace('\n', "\r\n"));
+    assert_eq!(Value::Struct(parse(&text)), Value::Struct(sample()));
+}
+
+#[test]
+fn compact_structs_stay_on_one_line() {
+    let options = WriteOptions {
+        indent: Indent::Spaces(2),
+        compact_structs: true,
+        ..WriteOptions::default()
+    };
+    let text = write_struct_with(&sample(), &options).unwrap();
+    assert!(text.contains("origin { x 1 }\n"), "{text}");
+    assert!(text.contains("size {\n  w 10\n  h 20\n}\n"), "{text}");
+    assert_eq!(Value::Struct(parse(&text)), Value::Struct(sample()));
+}
+
+#[test]
+fn wraps_long_arrays_of_scalars() {
+    let mut root = Struct::new();
+    root.insert(
+        "values".to_string(),
+        Value::Array((1..=12).map(|i| Value::Int(i * 100)).collect()),
+    )
+    .unwrap();
+    let options = WriteOptions {
+        max_width: 24,
+        ..WriteOptions::default()
+    };
+    let text = write_struct_with(&root, &options).unwrap();
+    assert_eq!(
+        text,
+        "values [\n    100 200 300 400 500\n    600 700 800 900 1000\n    1100 1200\n]\n"
+    );
+    assert!(text.lines().all(|line| line.len() <= 24), "{text}");
+    assert_eq!(Value::Struct(parse(&text)), Value::Struct(root));
+}